@@ -23,7 +23,8 @@ pub struct CityDefinition {
     #[factory(into)]
     name: String,
     population: u32,
-    #[factory(belongs_to(factory = "CountryFactory"))]
+    // Cities created without specifying their country all share the same one
+    #[factory(belongs_to(factory = "CountryFactory", strategy = "reuse_last"))]
     country: CountryId,
 }
 
@@ -1,7 +1,10 @@
 use fabriko::{BuildResource, Factory, FactoryContext, WithIdentifier};
 use nutype::nutype;
 
-use crate::{city::CityFactory, TestContext};
+use crate::{
+    city::{CityFactory, CityFactory__country},
+    TestContext,
+};
 
 #[nutype]
 #[derive(*)]
@@ -19,8 +22,16 @@ pub struct Country {
     factory = "CountryFactory",
     associations = "CountryFactoryAssociations"
 )]
-#[factory(has_one(factory = "CityFactory", name = "capital_city", link = "country"))]
-#[factory(has_many(factory = "CityFactory", name = "cities", link = "country"))]
+#[factory(has_one(
+    factory = "CityFactory",
+    name = "capital_city",
+    link = "CityFactory__country"
+))]
+#[factory(has_many(
+    factory = "CityFactory",
+    name = "cities",
+    link = "CityFactory__country"
+))]
 pub struct CountryDefinition {
     #[factory(into)]
     name: String,
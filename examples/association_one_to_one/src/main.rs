@@ -1,8 +1,8 @@
 use city::{CityFactory, CityId};
 use country::{CountryFactory, CountryId};
-use fabriko::{Fabriko, FactoryContext, WithRelatedResources};
+use fabriko::{Fabriko, FactoryContext, LastCreated, RememberLastCreated, WithRelatedResources};
 
-use crate::country::CountryFactoryAssociations;
+use crate::country::CountryFactoryAssociationsOutput;
 
 mod city;
 mod country;
@@ -14,6 +14,7 @@ mod country;
 pub struct TestContext {
     seq_city: i32,
     seq_country: i32,
+    last_created: LastCreated,
 }
 
 impl TestContext {
@@ -32,12 +33,18 @@ impl FactoryContext for TestContext {
     type Error = std::convert::Infallible;
 }
 
+impl RememberLastCreated for TestContext {
+    fn last_created(&mut self) -> &mut LastCreated {
+        &mut self.last_created
+    }
+}
+
 fn main() {
     let mut f = Factories::default();
 
     let (
         france,
-        CountryFactoryAssociations {
+        CountryFactoryAssociationsOutput {
             capital_city: paris,
             cities: french_cities,
         },
@@ -56,5 +63,10 @@ fn main() {
         city.name("Barcelona")
             .belonging_to_country(|country| country.name("Spain"))
     });
-    dbg!(barcelona);
+    dbg!(&barcelona);
+
+    // Barcelona's country is the last one used, so Madrid ends up in Spain too
+    let madrid = f.city(|city| city.name("Madrid"));
+    assert_eq!(madrid.country, barcelona.country);
+    dbg!(madrid);
 }
@@ -33,7 +33,7 @@ impl ExecuteAction<AppState> for GetUserGroup {
             .iter()
             .find(|ug| ug.id == self.user_group_id)
             .cloned()
-            .ok_or_else(|| GetUserGroupError::GroupDoesNotExist)?;
+            .ok_or(GetUserGroupError::GroupDoesNotExist)?;
         let user_ids: HashSet<UserId> = ctx
             .user_in_groups
             .iter()
@@ -51,7 +51,7 @@ impl ExecuteAction<AppState> for GetUserGroup {
             .filter(|u| user_ids.contains(&u.id))
             .cloned()
             .collect();
-        users.sort_by(|a, b| a.id.cmp(&b.id));
+        users.sort_by_key(|u| u.id);
         Ok(UserGroupDetails { user_group, users })
     }
 }
@@ -31,7 +31,7 @@ impl ExecuteAction<AppState> for UpdateTodo {
             .todos
             .iter_mut()
             .find(|t| t.id == self.todo_id)
-            .ok_or_else(|| UpdateTodoError::TodoDoesNotExist)?;
+            .ok_or(UpdateTodoError::TodoDoesNotExist)?;
 
         let TodoChangeset { title, done } = self.changeset;
 
@@ -57,13 +57,15 @@ impl ExecuteAction<AppState> for UpdateTodo {
 pub mod tests {
     use std::{cell::RefCell, rc::Rc};
 
+    use fabriko::proptest::{prop_assert, prop_assert_eq, proptest, strategy::Strategy};
+
     use crate::{
         actions::{
             todos::update::{TodoChangeset, UpdateTodo, UpdateTodoError},
             ExecuteAction,
         },
         context::{AppState, TestContext, TestContextFabriko},
-        models::todo::TodoId,
+        models::todo::{DoneTodoFactory, TodoFactory, TodoId},
     };
 
     #[test]
@@ -92,7 +94,7 @@ pub mod tests {
 
         assert_eq!(updated_todo.id, todo.id);
         assert_eq!(updated_todo.title, "My done todo".to_string());
-        assert_eq!(updated_todo.done, true);
+        assert!(updated_todo.done);
         assert_eq!(updated_todo.todo_group_id, todo.todo_group_id);
         assert_eq!(updated_todo.created_at, todo.created_at);
         assert_ne!(updated_todo.updated_at, todo.updated_at);
@@ -118,4 +120,52 @@ pub mod tests {
 
         assert_eq!(result, Err(UpdateTodoError::TodoDoesNotExist));
     }
+
+    proptest! {
+        #[test]
+        /// Whatever the todo, marking it as done keeps its title
+        fn should_keep_title_when_marking_todo_as_done(
+            todo_factory in TodoFactory::strategy().prop_map(|t| t.done(false))
+        ) {
+            let state = Rc::new(RefCell::new(AppState::default()));
+            let mut f = TestContextFabriko::new(TestContext::new(state.clone()));
+
+            let todo = f.todo(|_| todo_factory);
+
+            let updated_todo = UpdateTodo {
+                todo_id: todo.id,
+                changeset: TodoChangeset {
+                    title: None,
+                    done: Some(true),
+                },
+            }
+            .execute(&mut state.borrow_mut())
+            .expect("Failed to update todo");
+
+            prop_assert!(updated_todo.done);
+            prop_assert_eq!(updated_todo.title, todo.title);
+        }
+
+        #[test]
+        /// Child factories generate arbitrary todos as well
+        fn should_keep_title_when_reopening_todo(todo_factory in DoneTodoFactory::strategy()) {
+            let state = Rc::new(RefCell::new(AppState::default()));
+            let mut f = TestContextFabriko::new(TestContext::new(state.clone()));
+
+            let todo = f.done_todo(|_| todo_factory);
+
+            let updated_todo = UpdateTodo {
+                todo_id: todo.id,
+                changeset: TodoChangeset {
+                    title: None,
+                    done: Some(false),
+                },
+            }
+            .execute(&mut state.borrow_mut())
+            .expect("Failed to update todo");
+
+            prop_assert!(!updated_todo.done);
+            prop_assert_eq!(updated_todo.title, todo.title);
+        }
+    }
 }
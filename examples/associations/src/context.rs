@@ -3,7 +3,7 @@ use std::rc::Rc;
 
 use fabriko::{Fabriko, FactoryContext};
 
-use crate::models::todo::{Todo, TodoFactory, TodoId};
+use crate::models::todo::{DoneTodoFactory, Todo, TodoFactory, TodoId};
 use crate::models::todo_group::{TodoGroup, TodoGroupFactory, TodoGroupId};
 use crate::models::user::{User, UserFactory, UserId};
 use crate::models::user_group::{UserGroup, UserGroupFactory, UserGroupId, UserInGroup};
@@ -11,6 +11,7 @@ use crate::models::user_group::{UserGroup, UserGroupFactory, UserGroupId, UserIn
 #[derive(Debug, Default, Fabriko)]
 #[fabriko(wrapper = "TestContextFabriko")]
 #[fabriko(factory(factory = "TodoFactory", function = "todo"))]
+#[fabriko(factory(factory = "DoneTodoFactory", function = "done_todo"))]
 #[fabriko(factory(factory = "TodoGroupFactory", function = "todo_group"))]
 #[fabriko(factory(factory = "UserFactory", function = "user"))]
 #[fabriko(factory(factory = "UserGroupFactory", function = "user_group"))]
@@ -20,7 +21,7 @@ impl TestContext {
     pub fn new(state: Rc<RefCell<AppState>>) -> Self {
         Self(state)
     }
-    pub fn state(&mut self) -> RefMut<AppState> {
+    pub fn state(&mut self) -> RefMut<'_, AppState> {
         self.0.borrow_mut()
     }
 }
@@ -3,11 +3,11 @@
 //!
 //! * A `todo` belongs to a `todo_group` : there is a one-to-many relationship.
 //! * A `user` can belong to one or more `user_group`, and a `user_group` is
-//! composed of one or more `user` : there is a many-to-many relationship (the linking table is
-//! called `user_in_group`).
+//!   composed of one or more `user` : there is a many-to-many relationship (the linking table is
+//!   called `user_in_group`).
 //! * A `user` has exactly one `user_detail` that points to it : there is a one-to-one
-//! relationship (NOTE: Fabriko currently does *NOT* support one-to-one relationships where each entity
-//! points to the other).
+//!   relationship (NOTE: Fabriko currently does *NOT* support one-to-one relationships where each entity
+//!   points to the other).
 //!
 //! The resources can be represented by the following diagram :
 //!
@@ -41,26 +41,30 @@
 //!
 //! Fabriko allows the user to effortlessly create resources associated with each other :
 //! * By automatically declaring the "container" that this resource depends on, if relevant.
-//! The default attributes will be used, but it is easy to customize the "container" if needed.
+//!   The default attributes will be used, but it is easy to customize the "container" if needed.
 //! * By making it easy to create associated resources ("children") - e.g. create todos
-//! belonging to a group
+//!   belonging to a group
 //!
 
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use fabriko::dynamic::DynFactory;
 use fabriko::{Factory, FactoryBundle, WithRelatedResources};
 
 use context::TestContextFabriko;
-use models::todo::{Todo, TodoFactory};
+use models::todo::{DoneTodoFactory, Todo, TodoFactory};
 use models::todo_group::{TodoGroup, TodoGroupFactory};
 
 use crate::context::{AppState, TestContext};
-use crate::models::todo_group::TodoGroupAssociations;
+use crate::models::todo_group::TodoGroupAssociationsOutput;
 
+// Actions and some of the models are only exercised by the tests of this example
+#[cfg_attr(not(test), allow(dead_code))]
 mod actions;
 mod context;
 mod mixins;
+#[cfg_attr(not(test), allow(dead_code))]
 mod models;
 
 // When you want to share the same test setup between various tests, you can create
@@ -83,6 +87,105 @@ pub struct MyTestBundle {
     todo_two: Todo,
 }
 
+// Bundle fields are created in declaration order, and their attributes can refer to the fields
+// declared before them : here, bob's todo belongs to alice's group.
+#[derive(Debug, FactoryBundle)]
+pub struct SharedGroupBundle {
+    #[bundle(factory = "TodoGroupFactory", attributes(title = "\"Alice's group\""))]
+    alice_group: TodoGroup,
+    #[bundle(
+        factory = "TodoFactory",
+        attributes(
+            title = "format!(\"Bob's todo in {}\", alice_group.title)",
+            todo_group = "alice_group.id"
+        )
+    )]
+    bob_todo: Todo,
+}
+
+/// The parameters of `NamedGroupBundle`, to vary it from one test to another
+#[derive(Debug, Default)]
+pub struct NamedGroupParams {
+    title: String,
+}
+
+// Bundles can also be created with parameters, that their attributes read through `params`.
+#[derive(Debug, FactoryBundle)]
+#[bundle(params = "NamedGroupParams")]
+pub struct NamedGroupBundle {
+    #[bundle(
+        factory = "TodoGroupFactory",
+        attributes(title = "params.title.clone()")
+    )]
+    todo_group: TodoGroup,
+    #[bundle(
+        factory = "TodoFactory",
+        attributes(
+            title = "format!(\"First todo of {}\", params.title)",
+            todo_group = "todo_group.id"
+        )
+    )]
+    first_todo: Todo,
+}
+
+// Larger test worlds can be composed from smaller bundles : a field can itself be a bundle,
+// created with its default parameters or `with` the given ones. Declaring a base bundle first lets the
+// following fields refer to it, extending the base bundle.
+// A field can also gather `count` resources created by the same factory, the position of each
+// resource being available as `index`.
+#[derive(Debug, FactoryBundle)]
+pub struct TeamBundle {
+    #[bundle(bundle)]
+    base: SharedGroupBundle,
+    #[bundle(bundle(
+        params = "NamedGroupParams",
+        with = "NamedGroupParams { title: \"Chores\".to_string() }"
+    ))]
+    chores: NamedGroupBundle,
+    #[bundle(
+        factory = "TodoFactory",
+        count = "3",
+        attributes(
+            title = "format!(\"Team todo #{index}\")",
+            todo_group = "base.alice_group.id"
+        )
+    )]
+    team_todos: Vec<Todo>,
+}
+
+// Attributes are set in the order they are written. The factories of associations can be
+// customized as well : here, the todo belongs to a new group, titled "Inbox".
+#[derive(Debug, FactoryBundle)]
+pub struct InboxBundle {
+    #[bundle(
+        factory = "TodoFactory",
+        attributes(
+            title = "\"Triage\"",
+            belonging_to_todo_group(title = "\"Inbox\""),
+            done = "true"
+        )
+    )]
+    triage: Todo,
+}
+
+// Resources can also be created along with their related resources, or by any factory given by
+// `create`, e.g. a tuple of factories. Since the types of these factories can not be named, the
+// bundle has to be implemented for a given `context`.
+#[derive(Debug, FactoryBundle)]
+#[bundle(context = "TestContext")]
+pub struct RelatedResourcesBundle {
+    #[bundle(
+        factory = "TodoGroupFactory",
+        attributes(title = "\"Busy group\""),
+        related = "|related| related.with_todos_times(3)"
+    )]
+    busy_group: (TodoGroup, TodoGroupAssociationsOutput<Vec<Todo>>),
+    #[bundle(
+        create = "(TodoFactory::default().todo_group(busy_group.0.id), TodoGroupFactory::default())"
+    )]
+    pair: (Todo, TodoGroup),
+}
+
 fn main() {
     // Create a testing context and the wrapper that goes with it
     let state = Rc::new(RefCell::new(AppState::default()));
@@ -100,7 +203,7 @@ fn main() {
 
     // Alternatively, the user can create a container (group) and declare resources (todos)
     // that belongs to it. We then get access to those resources.
-    let (todo_group, TodoGroupAssociations { todos }) = f.todo_group(|tg| {
+    let (todo_group, TodoGroupAssociationsOutput { todos }) = f.todo_group(|tg| {
         tg.title("TG").with_related_resources(|tg| {
             tg.with_todos(|t| t.title("Todo one").done(true))
                 .with_todos(|t| t.title("Todo two"))
@@ -109,6 +212,17 @@ fn main() {
     dbg!(todo_group);
     dbg!(todos);
 
+    // Many resources can be declared at once, optionally customized using their index.
+    let (todo_group, TodoGroupAssociationsOutput { todos }) = f.todo_group(|tg| {
+        tg.title("Big TG").with_related_resources(|tg| {
+            tg.with_todos_times(2)
+                .with_todos_times_with(3, |index, t| t.title(format!("Todo #{index}")))
+        })
+    });
+    assert_eq!(todos.len(), 5);
+    dbg!(todo_group);
+    dbg!(todos);
+
     // You can also use bundles to create the container, and the two todos belonging to it
     let MyTestBundle {
         todo_group,
@@ -119,6 +233,52 @@ fn main() {
     dbg!(todo_one);
     dbg!(todo_two);
 
+    let SharedGroupBundle {
+        alice_group,
+        bob_todo,
+    } = f.bundle();
+    assert_eq!(bob_todo.todo_group_id, alice_group.id);
+    dbg!(alice_group);
+    dbg!(bob_todo);
+
+    let NamedGroupBundle {
+        todo_group,
+        first_todo,
+    } = f.bundle_with(NamedGroupParams {
+        title: "Groceries".to_string(),
+    });
+    assert_eq!(first_todo.title, "First todo of Groceries");
+    dbg!(todo_group);
+    dbg!(first_todo);
+
+    let TeamBundle {
+        base,
+        chores,
+        team_todos,
+    } = f.bundle();
+    assert_eq!(team_todos.len(), 3);
+    assert!(team_todos
+        .iter()
+        .all(|todo| todo.todo_group_id == base.alice_group.id));
+    dbg!(base);
+    dbg!(chores);
+    dbg!(team_todos);
+
+    let InboxBundle { triage } = f.bundle();
+    assert!(triage.done);
+    dbg!(triage);
+
+    let RelatedResourcesBundle {
+        busy_group: (busy_group, TodoGroupAssociationsOutput { todos }),
+        pair: (todo, empty_group),
+    } = f.bundle();
+    assert_eq!(todos.len(), 3);
+    assert_eq!(todo.todo_group_id, busy_group.id);
+    dbg!(busy_group);
+    dbg!(todos);
+    dbg!(todo);
+    dbg!(empty_group);
+
     // The user can easily create a resource that needs to belong to a container (here, a todo
     // belonging to a group), **without** explicitly defining the container. This is great to keep
     // your tests concise if you don't care about the container resource.
@@ -130,4 +290,52 @@ fn main() {
     let todo_in_named_group =
         f.todo(|t| t.belonging_to_todo_group(|group| group.title("Named group")));
     dbg!(todo_in_named_group);
+
+    // Data-driven tests can set the attributes of a factory by their name
+    let attributes: Vec<&str> = TodoFactory::attributes()
+        .iter()
+        .map(|attribute| attribute.name)
+        .collect();
+    assert_eq!(attributes, ["title", "done", "todo_group", "timestamps"]);
+    let dyn_group = f.todo_group(|tg| tg.title("Data-driven group"));
+    let dyn_todo = f.todo(|t| {
+        t.set_any("title", Box::new("Set by name".to_string()))
+            .and_then(|t| t.set_any("todo_group", Box::new(dyn_group.id)))
+            .expect("TodoFactory has a title and a todo group")
+    });
+    assert_eq!(dyn_todo.todo_group_id, dyn_group.id);
+    dbg!(dyn_todo);
+
+    // Factories can also be registered by name, e.g. for test generators, and create resources
+    // whose type is only known at runtime
+    f.register::<TodoFactory>("todo");
+    let registered_todo = f.create_dyn(
+        "todo",
+        vec![
+            ("title", Box::new("Created by name".to_string())),
+            ("done", Box::new(true)),
+        ],
+    );
+    let registered_todo: Box<Todo> = registered_todo
+        .downcast()
+        .expect("The todo factory creates todos");
+    assert!(registered_todo.done);
+    dbg!(registered_todo);
+
+    // Child factories share the setters, associations and `BuildResource` of their parent,
+    // only starting from different defaults
+    let done_todo = f.done_todo(|t| t.belonging_to_todo_group(|tg| tg.title("Done group")));
+    assert!(done_todo.done);
+    assert_eq!(done_todo.title, "My done todo");
+    let reopened_todo = f.done_todo(|t| t.title("Reopened todo").done(false));
+    assert!(!reopened_todo.done);
+    dbg!(done_todo, reopened_todo);
+
+    // They can be registered by name as well when their parent is `dynamic`
+    f.register::<DoneTodoFactory>("done_todo");
+    let registered_done_todo: Box<Todo> = f
+        .create_dyn("done_todo", vec![])
+        .downcast()
+        .expect("The done todo factory creates todos");
+    assert!(registered_done_todo.done);
 }
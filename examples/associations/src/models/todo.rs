@@ -22,12 +22,19 @@ pub struct Todo {
 }
 
 #[derive(Debug, Factory)]
-#[factory(factory = "TodoFactory", associations = "TodoAssociations")]
+#[factory(factory = "TodoFactory", associations = "TodoAssociations", dynamic)]
+// `DoneTodoFactory` creates todos as `TodoFactory` does, but they are done by default
+#[factory(child(
+    factory = "DoneTodoFactory",
+    defaults(title = "\"My done todo\"", done = "true")
+))]
+// Property tests generate arbitrary todos through `TodoFactory::strategy()`
+#[cfg_attr(test, factory(proptest))]
 pub struct TodoDefinition {
     #[factory(into, default = "\"My Todo\".to_string()")]
     title: String,
     done: bool,
-    #[factory(belongs_to(factory = "TodoGroupFactory"))]
+    #[factory(belongs_to(factory = "TodoGroupFactory", field = "todo_group_id"))]
     todo_group: TodoGroupId,
     #[factory(mixin)]
     timestamps: EditionTimestampMixin,
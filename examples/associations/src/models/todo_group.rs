@@ -6,7 +6,7 @@ use nutype::nutype;
 use crate::{context::TestContext, mixins::EditionTimestampMixin};
 
 use super::{
-    todo::TodoFactory,
+    todo::{TodoFactory, TodoFactory__todo_group},
     user::{UserFactory, UserId},
 };
 
@@ -26,7 +26,11 @@ pub struct TodoGroup {
 
 #[derive(Debug, Factory)]
 #[factory(factory = "TodoGroupFactory", associations = "TodoGroupAssociations")]
-#[factory(has_many(factory = "TodoFactory", link = "todo_group", name = "todos"))]
+#[factory(has_many(
+    factory = "TodoFactory",
+    link = "TodoFactory__todo_group",
+    name = "todos"
+))]
 pub struct TodoGroupDefinition {
     #[factory(into)]
     title: String,
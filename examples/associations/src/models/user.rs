@@ -16,7 +16,11 @@ pub struct User {
 
 #[derive(Debug, Factory)]
 #[factory(factory = "UserFactory")]
-#[factory(has_many(factory = "UserInGroupFactory", link = "user_id", name = "user_group"))]
+#[factory(has_many(
+    factory = "UserInGroupFactory",
+    link = "super::user_group::UserInGroupFactory__user_id",
+    name = "user_group"
+))]
 pub struct UserDefinition {
     #[factory(into)]
     name: String,
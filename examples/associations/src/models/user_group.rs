@@ -20,7 +20,7 @@ pub struct UserGroup {
 #[factory(factory = "UserGroupFactory", associations = "UserGroupAssociations")]
 #[factory(has_many(
     factory = "UserInGroupFactory",
-    link = "user_group_id",
+    link = "UserInGroupFactory__user_group_id",
     name = "user_in_group"
 ))]
 pub struct UserGroupDefinition {
@@ -1,4 +1,4 @@
-use fabriko::{BuildResource, Factory, FactoryContext};
+use fabriko::{Factory, FactoryContext};
 
 struct TestContext;
 impl FactoryContext for TestContext {
@@ -24,7 +24,8 @@ impl User {
 }
 
 #[derive(Debug, Factory)]
-#[factory(factory = "UserFactory")]
+// `output` derives `BuildResource`, moving every attribute to the same-named field of `User`
+#[factory(factory = "UserFactory", output = "User")]
 pub struct UserDefinition {
     #[factory(into, default = "\"Alice\".into()")]
     firstname: String,
@@ -34,23 +35,6 @@ pub struct UserDefinition {
     email: String,
 }
 
-impl BuildResource<TestContext> for UserDefinition {
-    type Output = User;
-
-    fn build_resource(self, _ctx: &mut TestContext) -> Result<Self::Output, ()> {
-        let UserDefinition {
-            firstname,
-            lastname,
-            email,
-        } = self;
-        Ok(User {
-            firstname,
-            lastname,
-            email,
-        })
-    }
-}
-
 fn main() {
     let mut cx = TestContext;
     let alice = UserFactory::default()
@@ -0,0 +1,159 @@
+use std::convert::Infallible;
+
+use fabriko::rand::Rng;
+use fabriko::{Fabriko, Factory, FactoryContext, FactoryContextRng, SeededRng};
+
+/// The context holds the random number generator used to generate `fake` attributes.
+///
+/// Seeding it makes the generated values reproducible : a failing test can be replayed
+/// by reusing the seed it was run with.
+#[derive(Debug, Default, Fabriko)]
+#[fabriko(
+    wrapper = "TestContextFabriko",
+    rng,
+    factory(factory = "UserFactory", function = "user"),
+    factory(factory = "OrderFactory", function = "order")
+)]
+struct TestContext {
+    rng: SeededRng,
+    seq_users: u32,
+}
+
+impl TestContext {
+    fn next_user_id(&mut self) -> u32 {
+        self.seq_users += 1;
+        self.seq_users
+    }
+}
+
+impl FactoryContext for TestContext {
+    type Error = Infallible;
+}
+
+impl FactoryContextRng for TestContext {
+    type Rng = SeededRng;
+
+    fn rng(&mut self) -> &mut Self::Rng {
+        &mut self.rng
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct User {
+    id: u32,
+    firstname: String,
+    lastname: String,
+    age: u8,
+    email: String,
+}
+
+#[derive(Debug, Factory)]
+// `output` derives `BuildResource`, moving every attribute to the same-named field of `User`,
+// and `id` gives the value of its `id` field
+#[factory(
+    factory = "UserFactory",
+    output = "User",
+    id = "ctx.next_user_id()",
+    context = "TestContext"
+)]
+pub struct UserDefinition {
+    // Unless set, `firstname` and `lastname` are generated using the fakers of the `fake` crate,
+    // which are created by calling the function of the same name
+    #[factory(into, fake = "faker::name::en::FirstName()")]
+    firstname: String,
+    #[factory(into, fake = "faker::name::en::LastName()")]
+    lastname: String,
+    // Any expression that evaluates to a faker can be used, e.g. a range
+    #[factory(fake = 18..99)]
+    age: u8,
+    // Dependant attributes can make use of generated attributes
+    #[factory(dependant = format!("{firstname}.{lastname}@test.com").to_lowercase())]
+    email: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Order {
+    reference: String,
+    quantity: u32,
+}
+
+// With `rng`, the `default` and `dependant` expressions can use the random number generator of
+// the context through `rng`. Default values are only computed when the attribute is left unset.
+#[derive(Debug, Factory)]
+#[factory(factory = "OrderFactory", rng, output = "Order")]
+pub struct OrderDefinition {
+    #[factory(dependant = format!("ORD-{:06}", rng.random_range(0..1_000_000)))]
+    reference: String,
+    #[factory(default = rng.random_range(1..10))]
+    quantity: u32,
+}
+
+fn main() {
+    let mut cx = TestContext {
+        rng: SeededRng::default(),
+        seq_users: 0,
+    };
+    println!("Running with {:?}", cx.rng);
+
+    let user = UserFactory::default()
+        .create(&mut cx)
+        .expect("Failed to create user");
+    dbg!(user);
+
+    // Explicitly set attributes are never generated
+    let alice = UserFactory::default()
+        .firstname("Alice")
+        .create(&mut cx)
+        .expect("Failed to create alice");
+    assert_eq!(alice.firstname, "Alice");
+    dbg!(alice);
+
+    // The same seed generates the same resources
+    let mut first_run = TestContext {
+        rng: SeededRng::new(42),
+        seq_users: 0,
+    };
+    let mut second_run = TestContext {
+        rng: SeededRng::new(42),
+        seq_users: 0,
+    };
+    assert_eq!(
+        UserFactory::default().create(&mut first_run),
+        UserFactory::default().create(&mut second_run),
+    );
+
+    // The wrapper seeds the context itself. When a resource fails to be created, the seed is
+    // printed, and the failure can be reproduced by running again with `FABRIKO_SEED=<seed>`.
+    let mut f = TestContextFabriko::with_seed(TestContext::default(), 42);
+    println!("Running with seed {:?}", f.seed());
+    let order = f.order(|o| o);
+    let big_order = f.order(|o| o.quantity(1000));
+    assert_eq!(big_order.quantity, 1000);
+    dbg!(order, big_order);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_generate_unset_attributes_with_fakers() {
+        use fabriko::fake::{faker::name::en::FirstName, Fake};
+
+        let mut fabriko = TestContextFabriko::with_seed(TestContext::default(), 1234);
+        let mut rng = SeededRng::new(fabriko.seed().expect("The wrapper is seeded"));
+        let user = fabriko.user(|u| u);
+        let firstname: String = FirstName().fake_with_rng(&mut rng);
+        assert_eq!(user.firstname, firstname);
+        assert!((18..99).contains(&user.age));
+    }
+
+    #[test]
+    fn should_create_the_same_resources_with_the_same_seed() {
+        let mut first_run = TestContextFabriko::with_seed(TestContext::default(), 1234);
+        let mut second_run = TestContextFabriko::with_seed(TestContext::default(), 1234);
+        assert_eq!(first_run.seed(), second_run.seed());
+        assert_eq!(first_run.user(|u| u), second_run.user(|u| u));
+        assert_eq!(first_run.order(|o| o), second_run.order(|o| o));
+    }
+}
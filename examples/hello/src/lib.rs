@@ -9,6 +9,8 @@ use fabriko::{BuildResource, Fabriko, Factory, FactoryContext};
 pub struct AppState {
     seq_persons: i32,
     persons: Vec<Person>,
+    seq_pets: i32,
+    pets: Vec<Pet>,
 }
 
 #[derive(Debug, Default, Fabriko)]
@@ -18,6 +20,10 @@ pub struct AppState {
 // This instructs Fabriko to add a `person` function. This will create a `PersonFactory`
 // for the user to customize, and the resource will then be persisted and returned.
 #[fabriko(factory(factory = "PersonFactory", function = "person"))]
+#[fabriko(factory(factory = "PetFactory", function = "pet"))]
+// This instructs Fabriko to keep a copy of every resource created through the wrapper,
+// so that they can be retrieved later on with `created` and `last`.
+#[fabriko(registry)]
 /// It is where all factories are declared.
 /// It contains whatever context we want to use to persist our resources.
 ///
@@ -55,6 +61,8 @@ impl FactoryContext for TestContext {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+// Persons are recorded in a snapshot by the tests, see `should_replay_snapshot`
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 /// The resource that we are going to build, as it is persisted in our application's state
 /// (In memory, or inside a database)
 pub struct Person {
@@ -65,8 +73,9 @@ pub struct Person {
 }
 
 #[derive(Factory)]
-// This instructs Fabriko to create a `PersonFactory` that will use the following definition
-#[factory(factory = "PersonFactory")]
+// This instructs Fabriko to create a `PersonFactory` that will use the following definition.
+// `dynamic` lets the factory be registered by name, to replay snapshots for example.
+#[factory(factory = "PersonFactory", dynamic)]
 /// The properties of our `PersonFactory`.
 /// This struct contains everything necessary to build a new instance of a `Person`.
 pub struct PersonDefinition {
@@ -117,6 +126,41 @@ impl BuildResource<TestContext> for PersonDefinition {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Another resource of our application
+pub struct Pet {
+    id: i32,
+    name: String,
+    species: String,
+    nickname: String,
+}
+
+// For quick fixtures, `factory!` declares the definition (here `PetDefinition`), the factory
+// and the way the resource is built all at once.
+//
+// Fields accept the same attributes as with `#[derive(Factory)]`. They can be given a default
+// value with `= expr`, or be computed from the other fields with `= dependant(expr)`.
+fabriko::factory! {
+    PetFactory for Pet in TestContext {
+        #[factory(into)]
+        name: String = "Rex".into(),
+        #[factory(into)]
+        species: String = "dog".into(),
+        nickname: String = dependant(format!("{name} the {species}")),
+    } => |PetDefinition { name, species, nickname }, ctx| {
+        let state = ctx.state();
+        state.seq_pets += 1;
+        let pet = Pet {
+            id: state.seq_pets,
+            name,
+            species,
+            nickname,
+        };
+        state.pets.push(pet.clone());
+        Ok(pet)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,4 +189,151 @@ mod tests {
         assert_eq!(state.seq_persons, person.id);
         assert_eq!(state.persons, vec![person]);
     }
+
+    #[test]
+    /// Checks that the wrapper keeps track of the persons it created.
+    fn should_register_created_persons() {
+        let mut context = TestContextFabriko::default();
+
+        let alice = context.person(|p| p.firstname("Alice"));
+        let bob = context.person(|p| p.firstname("Bob"));
+
+        assert_eq!(context.created::<Person>(), &[alice, bob.clone()]);
+        assert_eq!(context.last::<Person>(), Some(&bob));
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+    pub struct Toy {
+        name: String,
+        owner: i32,
+    }
+
+    impl fabriko::WithIdentifier for Toy {
+        type ID = String;
+
+        fn extract_id(&self) -> Self::ID {
+            self.name.clone()
+        }
+    }
+
+    impl fabriko::WithIdentifier for Person {
+        type ID = i32;
+
+        fn extract_id(&self) -> Self::ID {
+            self.id
+        }
+    }
+
+    fabriko::factory! {
+        #[factory(dynamic)]
+        ToyFactory for Toy in TestContext {
+            #[factory(into)]
+            name: String = "Ball".into(),
+            #[factory(belongs_to(factory = "PersonFactory"))]
+            owner: i32,
+        } => |ToyDefinition { name, owner }, _ctx| {
+            Ok(Toy { name, owner })
+        }
+    }
+
+    #[test]
+    /// Checks that the owner created along with a toy is kept in the registry as well.
+    fn should_register_parents_created_for_associations() {
+        let mut context = Fabriko::with_registry(TestContext::default());
+        context.track::<Person>();
+
+        let toy = context.factory(|t: ToyFactory| t.name("Ball"));
+        let alice = context.factory(|p: PersonFactory| p.firstname("Alice"));
+
+        assert_eq!(context.created::<Toy>(), std::slice::from_ref(&toy));
+        let persons = context.created::<Person>();
+        assert_eq!(persons.len(), 2);
+        assert_eq!(persons[0].id, toy.owner);
+        assert_eq!(persons[1], alice);
+    }
+
+    #[test]
+    /// Checks that a pet can be persisted using the factory declared with `factory!`.
+    fn should_create_pet() {
+        let mut context = TestContextFabriko::default();
+
+        let pet = context.pet(|p| p.name("Felix").species("cat"));
+        assert_eq!(
+            pet,
+            Pet {
+                id: 1,
+                name: "Felix".into(),
+                species: "cat".into(),
+                nickname: "Felix the cat".into(),
+            }
+        );
+        assert_eq!(context.into_inner().into_app_state().pets, vec![pet]);
+    }
+
+    #[test]
+    /// Checks that the persons recorded in a snapshot can be created again in another context.
+    fn should_replay_snapshot() {
+        use fabriko::snapshot::Snapshot;
+
+        let mut context = Fabriko::with_snapshot(TestContext::default());
+        context.register_serializable::<PersonFactory>("person");
+        context.factory(|p: PersonFactory| p.firstname("Alice").lastname("Cooper"));
+        context.factory(|p: PersonFactory| p.firstname("Bob").age(42));
+        let snapshot =
+            Snapshot::from_json(&context.snapshot().to_json()).expect("Failed to read snapshot");
+        assert_eq!(snapshot.entries().len(), 2);
+
+        let mut replayed = Fabriko::new(TestContext::default());
+        replayed.register::<PersonFactory>("person");
+        replayed
+            .replay(&snapshot)
+            .expect("Failed to replay snapshot");
+        assert_eq!(
+            replayed.into_inner().into_app_state().persons,
+            context.into_inner().into_app_state().persons
+        );
+    }
+
+    #[test]
+    /// Checks that replayed toys belong to the replayed persons, including the owners created
+    /// along with the toys, even though the persons are given other identifiers.
+    fn should_replay_owners_of_toys() {
+        use fabriko::snapshot::Snapshot;
+
+        let mut context = Fabriko::with_snapshot(TestContext::default());
+        context
+            .register_serializable::<PersonFactory>("person")
+            .register_serializable::<ToyFactory>("toy");
+        context.factory(|t: ToyFactory| t.name("Ball"));
+        let alice = context.factory(|p: PersonFactory| p.firstname("Alice"));
+        context.factory(|t: ToyFactory| t.name("Doll").owner(alice.id));
+        let snapshot =
+            Snapshot::from_json(&context.snapshot().to_json()).expect("Failed to read snapshot");
+        let factories: Vec<&str> = snapshot
+            .entries()
+            .iter()
+            .map(|entry| entry.factory.as_str())
+            .collect();
+        assert_eq!(factories, ["person", "toy", "person", "toy"]);
+
+        let mut replayed = Fabriko::with_registry(TestContext::default());
+        replayed.factory(|p: PersonFactory| p.firstname("Zoe"));
+        replayed
+            .track::<Toy>()
+            .register::<PersonFactory>("person")
+            .register::<ToyFactory>("toy");
+        replayed
+            .replay(&snapshot)
+            .expect("Failed to replay snapshot");
+
+        let persons = replayed.created::<Person>();
+        assert_eq!(persons.len(), 3);
+        assert_eq!(persons[2].firstname, "Alice");
+        let owners: Vec<(&str, i32)> = replayed
+            .created::<Toy>()
+            .iter()
+            .map(|toy| (toy.name.as_str(), toy.owner))
+            .collect();
+        assert_eq!(owners, [("Ball", persons[1].id), ("Doll", persons[2].id)]);
+    }
 }
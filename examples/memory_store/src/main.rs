@@ -0,0 +1,86 @@
+//! Prototypes and unit tests do not need to write their own context : `MemoryStore` keeps the
+//! created resources in memory, and `#[factory(memory)]` derives how they are built and stored.
+//!
+//! With `#[factory(dynamic)]`, the same factories create the resources described by fixture files.
+
+use fabriko::memory::{Id, MemoryStore};
+use fabriko::{Fabriko, Factory, WithIdentifier};
+
+#[derive(Debug, Clone, PartialEq, WithIdentifier)]
+pub struct Person {
+    #[identifier]
+    id: Id<Person>,
+    name: String,
+}
+
+#[derive(Factory)]
+#[factory(factory = "PersonFactory", output = "Person", memory, dynamic)]
+pub struct PersonDefinition {
+    #[factory(into, default = "\"Alice\".into()")]
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, WithIdentifier)]
+pub struct Pet {
+    #[identifier]
+    id: Id<Pet>,
+    name: String,
+    owner: Id<Person>,
+}
+
+#[derive(Factory)]
+#[factory(factory = "PetFactory", output = "Pet", memory, dynamic)]
+pub struct PetDefinition {
+    #[factory(into, default = "\"Rex\".into()")]
+    name: String,
+    // Unless specified, a new owner is created along with the pet
+    #[factory(belongs_to(factory = "PersonFactory"))]
+    owner: Id<Person>,
+}
+
+// The owner of a pet refers to another fixture, created first
+const FIXTURES: &str = r#"
+pets:
+  garfield:
+    factory: PetFactory
+    name: Garfield
+    owner: $persons.jon
+persons:
+  jon:
+    factory: PersonFactory
+    name: Jon
+"#;
+
+fn main() {
+    let mut f = Fabriko::new(MemoryStore::default());
+
+    let bob = f.factory(|p: PersonFactory| p.name("Bob"));
+    let rex = f.factory(|p: PetFactory| p.owner(bob.id));
+    let felix = f.factory(|p: PetFactory| p.name("Felix"));
+    dbg!(&bob, &rex, &felix);
+
+    // The resources can then be queried from the store
+    let store = f.into_inner();
+    assert_eq!(store.count::<Person>(), 2);
+    assert_eq!(
+        store.get::<Person>(&felix.owner).map(|p| p.name.as_str()),
+        Some("Alice")
+    );
+    assert_eq!(store.filter(|pet: &&Pet| pet.owner == bob.id), vec![&rex]);
+
+    // Fixtures are created by the factories registered on the wrapper
+    let mut f = Fabriko::new(MemoryStore::default());
+    f.register::<PersonFactory>("PersonFactory")
+        .register::<PetFactory>("PetFactory");
+    let loaded = match f.load_fixtures_yaml(FIXTURES) {
+        Ok(loaded) => loaded,
+        Err(err) => panic!("{err}"),
+    };
+    let store = f.into_inner();
+    let garfield = loaded
+        .id::<Id<Pet>>("pets.garfield")
+        .and_then(|id| store.get::<Pet>(&id))
+        .expect("Garfield was created");
+    assert_eq!(loaded.id::<Id<Person>>("persons.jon"), Some(garfield.owner));
+    dbg!(store.all::<Person>(), store.all::<Pet>());
+}
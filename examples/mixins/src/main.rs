@@ -1,4 +1,4 @@
-use fabriko::{BuildResource, Factory, FactoryBundle, FactoryContext, Mixin};
+use fabriko::{BuildResource, Fabriko, Factory, FactoryBundle, FactoryContext, Mixin};
 use std::time::Instant;
 
 struct TestContext;
@@ -101,4 +101,9 @@ fn main() {
     let MyTestSetup { alice, bob } =
         MyTestSetup::create_bundle(&mut cx).expect("Failed to create MyTestSetup");
     dbg!(alice, bob);
+
+    // The Fabriko wrapper works with any context whose error implements Debug, even `()`
+    let mut f = Fabriko::new(cx);
+    let MyTestSetup { alice, bob } = f.bundle();
+    dbg!(alice, bob);
 }
@@ -1,20 +1,25 @@
+use std::marker::PhantomData;
+
 use crate::{
     factory::{Factory, FactoryContext},
     tuple_hack::UnitTuple,
-    FactorySetter, WithIdentifier,
+    FabrikoError, FactorySetter, WithIdentifier,
 };
 
 use super::ResolveDependency;
 
 #[derive(Debug)]
 pub enum BelongsTo<F, ID> {
+    /// The resource was left unspecified, it is resolved according to the
+    /// [`BelongsToStrategy`](crate::BelongsToStrategy) of the field
+    Default(F),
     Create(F),
     Created(ID),
 }
 
 impl<F: Default, ID> Default for BelongsTo<F, ID> {
     fn default() -> Self {
-        BelongsTo::Create(F::default())
+        BelongsTo::Default(F::default())
     }
 }
 
@@ -23,41 +28,55 @@ where
     <F as Factory<CTX>>::Output: WithIdentifier<ID = ID>,
 {
     type Output = ID;
-    fn resolve_dependency(self, cx: &mut CTX) -> Result<Self::Output, CTX::Error> {
+    fn resolve_dependency(self, cx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         let id = match self {
-            BelongsTo::Create(factory) => factory.create(cx)?.extract_id(),
+            BelongsTo::Default(factory) | BelongsTo::Create(factory) => {
+                factory.create(cx)?.extract_id()
+            }
             BelongsTo::Created(id) => id,
         };
         Ok(id)
     }
 }
 
-pub trait BelongingToLink<const N: u64> {
+/// Links a factory to one of its `belongs_to` fields.
+///
+/// `LINK` is a zero-sized marker type generated by `#[derive(Factory)]` for each `belongs_to`
+/// field, e.g. `TodoFactory__todo_group` for the `todo_group` field of `TodoFactory`.
+pub trait BelongingToLink<LINK> {
     type ID;
     const SETTER: FactorySetter<Self, Self::ID>;
 }
 
 #[derive(Debug)]
-pub struct FactoryBelongingTo<const N: u64, F> {
+pub struct FactoryBelongingTo<LINK, F> {
     pub factory: F,
+    link: PhantomData<LINK>,
 }
 
-impl<const N: u64, R: WithIdentifier, F: BelongingToLink<N, ID = <R as WithIdentifier>::ID>>
-    BelongingTo<R> for FactoryBelongingTo<N, F>
+impl<LINK, F> FactoryBelongingTo<LINK, F> {
+    pub fn new(factory: F) -> Self {
+        FactoryBelongingTo {
+            factory,
+            link: PhantomData,
+        }
+    }
+}
+
+impl<LINK, R: WithIdentifier, F: BelongingToLink<LINK, ID = <R as WithIdentifier>::ID>>
+    BelongingTo<R> for FactoryBelongingTo<LINK, F>
 {
     fn belonging_to(self, resource: &R) -> Self {
-        let FactoryBelongingTo { factory } = self;
-        let factory = BelongingToLink::<{ N }>::SETTER(factory, resource.extract_id());
-        FactoryBelongingTo { factory }
+        let FactoryBelongingTo { factory, link } = self;
+        let factory = BelongingToLink::<LINK>::SETTER(factory, resource.extract_id());
+        FactoryBelongingTo { factory, link }
     }
 }
 
-impl<const N: u64, CTX: FactoryContext, F: Factory<CTX>> Factory<CTX>
-    for FactoryBelongingTo<{ N }, F>
-{
+impl<LINK, CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for FactoryBelongingTo<LINK, F> {
     type Output = <F as Factory<CTX>>::Output;
 
-    fn create(self, ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+    fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         self.factory.create(ctx)
     }
 }
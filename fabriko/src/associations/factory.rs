@@ -1,4 +1,4 @@
-use crate::{BelongingTo, Factory, FactoryContext};
+use crate::{BelongingTo, FabrikoError, Factory, FactoryContext};
 
 /// TODO: Documentation
 pub struct FactoryWithResources<F, R> {
@@ -13,7 +13,7 @@ where
 {
     type Output = (<F as Factory<CTX>>::Output, <R as Factory<CTX>>::Output);
 
-    fn create(self, ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+    fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         let FactoryWithResources { factory, resources } = self;
         let resource = factory.create(ctx)?;
         let resources = resources.belonging_to(&resource).create(ctx)?;
@@ -1,28 +1,60 @@
 use crate::factory::{Factory, FactoryContext};
-use crate::{BelongingTo, BelongingToLink, FactoryBelongingTo, WithIdentifier};
+use crate::{
+    BelongingTo, BelongingToLink, FabrikoError, FactoryBelongingTo, PathSegment, WithIdentifier,
+};
 
-#[derive(Default)]
-pub struct HasMany<const N: u64, F>(Vec<FactoryBelongingTo<N, F>>);
+/// The resources of a `has_many` association, each one declared from the default factory `F`.
+///
+/// As `F` is customized without changing its type, it can not have `#[factory(required)]`
+/// attributes, see [`Required`](crate::Required).
+pub struct HasMany<LINK, F>(Vec<FactoryBelongingTo<LINK, F>>);
 
-impl<const N: u64, F: Default> HasMany<N, F> {
-    pub fn with<const LINK: u64, FF: FnOnce(F) -> F>(mut self, func: FF) -> Self {
-        self.0.push(FactoryBelongingTo {
-            factory: func(Default::default()),
-        });
+impl<LINK, F> Default for HasMany<LINK, F> {
+    fn default() -> Self {
+        HasMany(Vec::new())
+    }
+}
+
+impl<LINK, F: Default> HasMany<LINK, F> {
+    pub fn with<FF: FnOnce(F) -> F>(mut self, func: FF) -> Self {
+        self.0
+            .push(FactoryBelongingTo::new(func(Default::default())));
+        self
+    }
+
+    /// Declares `count` more resources, built with the default attributes of the factory
+    pub fn times(self, count: usize) -> Self {
+        self.times_with(count, |_, factory| factory)
+    }
+
+    /// Declares `count` more resources, each customized by `func`.
+    /// The index given to `func` goes from `0` to `count - 1`, regardless of the resources
+    /// that may have been declared before.
+    pub fn times_with<FF: FnMut(usize, F) -> F>(mut self, count: usize, mut func: FF) -> Self {
+        self.0.extend(
+            (0..count).map(|index| FactoryBelongingTo::new(func(index, Default::default()))),
+        );
         self
     }
 }
 
-impl<const N: u64, CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for HasMany<N, F> {
+impl<LINK, CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for HasMany<LINK, F> {
     type Output = Vec<F::Output>;
 
-    fn create(self, ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
-        self.0.into_iter().map(|f| f.create(ctx)).collect()
+    fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
+        self.0
+            .into_iter()
+            .enumerate()
+            .map(|(index, f)| {
+                f.create(ctx)
+                    .map_err(|err| err.context(PathSegment::Index(index)))
+            })
+            .collect()
     }
 }
 
-impl<const N: u64, R: WithIdentifier, F: BelongingToLink<N, ID = <R as WithIdentifier>::ID>>
-    BelongingTo<R> for HasMany<N, F>
+impl<LINK, R: WithIdentifier, F: BelongingToLink<LINK, ID = <R as WithIdentifier>::ID>>
+    BelongingTo<R> for HasMany<LINK, F>
 {
     fn belonging_to(self, resource: &R) -> Self {
         let factories_belonging_to = self
@@ -1,5 +1,6 @@
 use crate::{
-    BelongingTo, BelongingToLink, Factory, FactoryBelongingTo, FactoryContext, WithIdentifier,
+    BelongingTo, BelongingToLink, FabrikoError, Factory, FactoryBelongingTo, FactoryContext,
+    WithIdentifier,
 };
 
 #[derive(Debug, Default)]
@@ -8,7 +9,7 @@ pub struct HasOneDefault<F>(F);
 impl<CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for HasOneDefault<F> {
     type Output = <F as Factory<CTX>>::Output;
 
-    fn create(self, ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+    fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         self.0.create(ctx)
     }
 }
@@ -31,7 +32,7 @@ impl<ID> HasOneCreated<ID> {
 impl<CTX: FactoryContext, ID> Factory<CTX> for HasOneCreated<ID> {
     type Output = ID;
 
-    fn create(self, _ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+    fn create(self, _ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         Ok(self.0)
     }
 }
@@ -43,27 +44,27 @@ impl<R, ID> BelongingTo<R> for HasOneCreated<ID> {
 }
 
 #[derive(Debug)]
-pub struct HasOneToCreate<const N: u64, F>(FactoryBelongingTo<N, F>);
+pub struct HasOneToCreate<LINK, F>(FactoryBelongingTo<LINK, F>);
 
-impl<const N: u64, F> HasOneToCreate<N, F> {
+impl<LINK, F> HasOneToCreate<LINK, F> {
     pub fn new(factory: F) -> Self {
-        HasOneToCreate(FactoryBelongingTo { factory })
+        HasOneToCreate(FactoryBelongingTo::new(factory))
     }
 }
 
-impl<const N: u64, CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for HasOneToCreate<N, F>
+impl<LINK, CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for HasOneToCreate<LINK, F>
 where
     <F as Factory<CTX>>::Output: WithIdentifier,
 {
     type Output = <<F as Factory<CTX>>::Output as WithIdentifier>::ID;
 
-    fn create(self, cx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+    fn create(self, cx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         self.0.create(cx).map(|resource| resource.extract_id())
     }
 }
 
-impl<const N: u64, R: WithIdentifier, F: BelongingToLink<N, ID = <R as WithIdentifier>::ID>>
-    BelongingTo<R> for HasOneToCreate<N, F>
+impl<LINK, R: WithIdentifier, F: BelongingToLink<LINK, ID = <R as WithIdentifier>::ID>>
+    BelongingTo<R> for HasOneToCreate<LINK, F>
 {
     fn belonging_to(mut self, resource: &R) -> Self {
         self.0 = self.0.belonging_to(resource);
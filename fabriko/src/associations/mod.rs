@@ -1,4 +1,4 @@
-use crate::FactoryContext;
+use crate::{FabrikoError, FactoryContext};
 
 use self::factory::FactoryWithResources;
 
@@ -7,11 +7,12 @@ pub mod factory;
 pub mod has_many;
 pub mod has_one;
 // pub mod may_belong_to;
+pub mod strategy;
 pub mod with_identifier;
 
 pub trait ResolveDependency<CTX: FactoryContext> {
     type Output;
-    fn resolve_dependency(self, cx: &mut CTX) -> Result<Self::Output, CTX::Error>;
+    fn resolve_dependency(self, cx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>>;
 }
 
 pub trait WithRelatedResources: Sized {
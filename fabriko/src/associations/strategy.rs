@@ -0,0 +1,214 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+use crate::{BelongsTo, FabrikoError, Factory, FactoryContext, ResolveDependency, WithIdentifier};
+
+/// Defines how the resource a factory belongs to is resolved when the factory is created.
+///
+/// The strategy of a field is selected with
+/// `#[factory(belongs_to(factory = "..", strategy = ".."))]`.
+pub trait BelongsToStrategy<CTX: FactoryContext, F, ID> {
+    fn resolve(belongs_to: BelongsTo<F, ID>, cx: &mut CTX) -> Result<ID, FabrikoError<CTX::Error>>;
+}
+
+/// `strategy = "create"` (the default) : a new resource is created every time
+/// the belonging factory is created.
+#[derive(Debug)]
+pub struct CreateStrategy;
+
+impl<CTX: FactoryContext, F: Factory<CTX>, ID> BelongsToStrategy<CTX, F, ID> for CreateStrategy
+where
+    <F as Factory<CTX>>::Output: WithIdentifier<ID = ID>,
+{
+    fn resolve(belongs_to: BelongsTo<F, ID>, cx: &mut CTX) -> Result<ID, FabrikoError<CTX::Error>> {
+        belongs_to.resolve_dependency(cx)
+    }
+}
+
+/// `strategy = "reuse_last"` : when left unspecified, the resource is the last one that was used
+/// by a factory belonging to `F` with this strategy. It is only created if there is none yet.
+///
+/// The context remembers the last resource through [`RememberLastCreated`].
+///
+/// Only the resources resolved through this strategy are remembered : a resource created
+/// directly by its factory, or through a field using another strategy, is not reused.
+/// Call [`LastCreated::set`] to reuse such a resource.
+#[derive(Debug)]
+pub struct ReuseLastStrategy;
+
+impl<CTX, F, ID> BelongsToStrategy<CTX, F, ID> for ReuseLastStrategy
+where
+    CTX: RememberLastCreated,
+    F: Factory<CTX> + 'static,
+    <F as Factory<CTX>>::Output: WithIdentifier<ID = ID>,
+    ID: Clone + 'static,
+{
+    fn resolve(belongs_to: BelongsTo<F, ID>, cx: &mut CTX) -> Result<ID, FabrikoError<CTX::Error>> {
+        let id = match belongs_to {
+            BelongsTo::Default(factory) => match cx.last_created().get::<F, ID>() {
+                Some(id) => id,
+                None => factory.create(cx)?.extract_id(),
+            },
+            belongs_to => belongs_to.resolve_dependency(cx)?,
+        };
+        cx.last_created().set::<F, ID>(id.clone());
+        Ok(id)
+    }
+}
+
+/// `strategy = "find_or_create"` : when left unspecified, the context is asked to find an
+/// existing resource through [`FindExisting`]. It is only created if none could be found.
+#[derive(Debug)]
+pub struct FindOrCreateStrategy;
+
+impl<CTX, F, ID> BelongsToStrategy<CTX, F, ID> for FindOrCreateStrategy
+where
+    CTX: FindExisting<F, ID = ID>,
+    F: Factory<CTX>,
+    <F as Factory<CTX>>::Output: WithIdentifier<ID = ID>,
+{
+    fn resolve(belongs_to: BelongsTo<F, ID>, cx: &mut CTX) -> Result<ID, FabrikoError<CTX::Error>> {
+        match belongs_to {
+            BelongsTo::Default(factory) => match cx.find_existing()? {
+                Some(id) => Ok(id),
+                None => Ok(factory.create(cx)?.extract_id()),
+            },
+            belongs_to => belongs_to.resolve_dependency(cx),
+        }
+    }
+}
+
+/// Implemented by contexts that can look up an existing resource that the factory `F`
+/// would otherwise create, e.g. by querying a database.
+pub trait FindExisting<F>: FactoryContext {
+    type ID;
+    fn find_existing(&mut self) -> Result<Option<Self::ID>, Self::Error>;
+}
+
+/// Implemented by contexts that support [`ReuseLastStrategy`].
+pub trait RememberLastCreated: FactoryContext {
+    fn last_created(&mut self) -> &mut LastCreated;
+}
+
+/// The identifiers of the last resources used through [`ReuseLastStrategy`],
+/// keyed by the type of the factory that creates them.
+///
+/// The resources created otherwise are not remembered unless they are [`set`](LastCreated::set).
+#[derive(Debug, Default)]
+pub struct LastCreated(HashMap<TypeId, Box<dyn Any>>);
+
+impl LastCreated {
+    pub fn get<F: 'static, ID: Clone + 'static>(&self) -> Option<ID> {
+        self.0
+            .get(&TypeId::of::<F>())
+            .and_then(|id| id.downcast_ref::<ID>())
+            .cloned()
+    }
+
+    pub fn set<F: 'static, ID: 'static>(&mut self, id: ID) {
+        self.0.insert(TypeId::of::<F>(), Box::new(id));
+    }
+
+    /// Forgets every remembered resource, so that the next ones get created again
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct TestContext {
+        groups: Vec<usize>,
+        last_created: LastCreated,
+    }
+
+    impl FactoryContext for TestContext {
+        type Error = Infallible;
+    }
+
+    impl RememberLastCreated for TestContext {
+        fn last_created(&mut self) -> &mut LastCreated {
+            &mut self.last_created
+        }
+    }
+
+    impl FindExisting<GroupFactory> for TestContext {
+        type ID = usize;
+
+        fn find_existing(&mut self) -> Result<Option<usize>, Infallible> {
+            Ok(self.groups.first().copied())
+        }
+    }
+
+    struct Group {
+        id: usize,
+    }
+
+    impl WithIdentifier for Group {
+        type ID = usize;
+
+        fn extract_id(&self) -> usize {
+            self.id
+        }
+    }
+
+    #[derive(Default)]
+    struct GroupFactory;
+
+    impl Factory<TestContext> for GroupFactory {
+        type Output = Group;
+
+        fn create(self, ctx: &mut TestContext) -> Result<Group, FabrikoError<Infallible>> {
+            let id = ctx.groups.len() + 1;
+            ctx.groups.push(id);
+            Ok(Group { id })
+        }
+    }
+
+    #[test]
+    fn reuse_last_should_share_one_parent_between_siblings() {
+        let mut ctx = TestContext::default();
+        let ids: Vec<usize> = (0..10)
+            .map(|_| ReuseLastStrategy::resolve(BelongsTo::<GroupFactory, _>::default(), &mut ctx))
+            .collect::<Result<_, _>>()
+            .expect("Failed to resolve parents");
+        assert_eq!(ids, vec![1; 10]);
+        assert_eq!(ctx.groups, vec![1]);
+    }
+
+    #[test]
+    fn reuse_last_should_remember_explicit_parents() {
+        let mut ctx = TestContext::default();
+        let created = ReuseLastStrategy::resolve(BelongsTo::Create(GroupFactory), &mut ctx);
+        assert_eq!(created, Ok(1));
+        let reused = ReuseLastStrategy::resolve(BelongsTo::<GroupFactory, _>::default(), &mut ctx);
+        assert_eq!(reused, Ok(1));
+
+        ctx.last_created().clear();
+        let recreated =
+            ReuseLastStrategy::resolve(BelongsTo::<GroupFactory, _>::default(), &mut ctx);
+        assert_eq!(recreated, Ok(2));
+    }
+
+    #[test]
+    fn find_or_create_should_reuse_an_existing_match() {
+        let mut ctx = TestContext::default();
+        let created =
+            FindOrCreateStrategy::resolve(BelongsTo::<GroupFactory, _>::default(), &mut ctx);
+        assert_eq!(created, Ok(1));
+        let found =
+            FindOrCreateStrategy::resolve(BelongsTo::<GroupFactory, _>::default(), &mut ctx);
+        assert_eq!(found, Ok(1));
+        assert_eq!(ctx.groups, vec![1]);
+
+        let explicit = FindOrCreateStrategy::resolve(BelongsTo::Create(GroupFactory), &mut ctx);
+        assert_eq!(explicit, Ok(2));
+    }
+}
@@ -1,5 +1,19 @@
-use crate::FactoryContext;
+use crate::{FabrikoError, FactoryContext};
 
 pub trait FactoryBundle<CTX: FactoryContext>: Sized {
-    fn create_bundle(cx: &mut CTX) -> Result<Self, CTX::Error>;
+    /// The parameters the bundle is created with, `()` when it has none
+    type Params;
+
+    fn create_bundle_with(
+        cx: &mut CTX,
+        params: Self::Params,
+    ) -> Result<Self, FabrikoError<CTX::Error>>;
+
+    /// Creates the bundle with the default parameters
+    fn create_bundle(cx: &mut CTX) -> Result<Self, FabrikoError<CTX::Error>>
+    where
+        Self::Params: Default,
+    {
+        Self::create_bundle_with(cx, Default::default())
+    }
 }
@@ -1,46 +1,266 @@
-use crate::{Factory, FactoryBundle, FactoryContext};
+use std::any::Any;
 
+use crate::{
+    dynamic::{DynAttributes, DynCreateError, DynFactories, DynFactory},
+    registry::Recorder,
+    FabrikoError, Factory, FactoryBundle, FactoryContext, Registry, WithIdentifier,
+};
+
+/// The entrypoint to create resources on a context.
+///
+/// By default, created resources are only returned to the caller.
+/// A wrapper created with [`Fabriko::with_registry`] also keeps a copy of them in a [`Registry`],
+/// which requires every created resource to be `Clone`. The resources created for their
+/// associations are kept as well once their type is [tracked](Fabriko::track).
+///
+/// A wrapper created with `Fabriko::with_snapshot` also records the resources created by the
+/// factories registered with `Fabriko::register_serializable` in a
+/// [`Snapshot`](crate::snapshot::Snapshot), including the ones created for associations.
+///
+/// A wrapper created with `Fabriko::with_seed` seeds the random number generator of the context,
+/// and prints the seed when a resource fails to be created, or when creating it panics, so that
+/// the failure can be reproduced.
+///
+/// Factories can also be registered by name with [`Fabriko::register`], to create resources
+/// with [`Fabriko::create_dyn`] when their type is only known at runtime, to load fixture files
+/// or to replay snapshots.
 #[derive(Debug, Default)]
-pub struct Fabriko<CTX>(CTX);
+pub struct Fabriko<CTX: FactoryContext, REGISTRY = ()> {
+    ctx: CTX,
+    registry: REGISTRY,
+    seed: Option<u64>,
+    factories: DynFactories<CTX>,
+}
 
-impl<CTX: FactoryContext> Fabriko<CTX>
+impl<CTX: FactoryContext, REGISTRY: Recorder> Fabriko<CTX, REGISTRY>
 where
-    CTX::Error: std::error::Error,
+    CTX::Error: std::fmt::Debug,
 {
     pub fn into_inner(self) -> CTX {
-        self.0
+        self.ctx
     }
 
-    pub fn new(ctx: CTX) -> Self {
-        Fabriko(ctx)
+    /// The seed of the random number generator of the context, if it was seeded by this wrapper
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
     }
 
-    pub fn bundle<B: FactoryBundle<CTX>>(&mut self) -> B {
-        match B::create_bundle(&mut self.0) {
+    pub fn bundle<B: FactoryBundle<CTX>>(&mut self) -> B
+    where
+        B::Params: Default,
+    {
+        self.bundle_with(Default::default())
+    }
+
+    /// Creates the bundle `B` with the given parameters
+    pub fn bundle_with<B: FactoryBundle<CTX>>(&mut self, params: B::Params) -> B {
+        match self.try_bundle_with(params) {
             Ok(bundle) => bundle,
             Err(err) => panic!(
-                "Failed to create bundle {} : {}",
+                "Failed to create bundle {} : {}{}",
                 std::any::type_name::<B>(),
-                err
+                err.describe(),
+                self.seed_hint()
+            ),
+        }
+    }
+
+    pub fn try_bundle<B: FactoryBundle<CTX>>(&mut self) -> Result<B, FabrikoError<CTX::Error>>
+    where
+        B::Params: Default,
+    {
+        self.try_bundle_with(Default::default())
+    }
+
+    pub fn try_bundle_with<B: FactoryBundle<CTX>>(
+        &mut self,
+        params: B::Params,
+    ) -> Result<B, FabrikoError<CTX::Error>> {
+        let _seed_hint = SeedHint(self.seed);
+        let ctx = &mut self.ctx;
+        self.registry
+            .record_during(|| B::create_bundle_with(ctx, params))
+    }
+
+    /// Lets `F` create resources by `name` with [`Fabriko::create_dyn`].
+    pub fn register<F>(&mut self, name: &'static str) -> &mut Self
+    where
+        F: DynFactory + Factory<CTX> + Default + 'static,
+        F::Output: WithIdentifier + 'static,
+        <F::Output as WithIdentifier>::ID: 'static,
+    {
+        self.factories.register::<F>(name);
+        self
+    }
+
+    /// Registers `F` by `name` like [`Fabriko::register`], and records the resources it creates
+    /// under that name when the wrapper records a [`Snapshot`](crate::snapshot::Snapshot)
+    #[cfg(feature = "serde")]
+    pub fn register_serializable<F>(&mut self, name: &'static str) -> &mut Self
+    where
+        F: DynFactory + Factory<CTX> + Default + 'static,
+        F::Output: serde::Serialize + WithIdentifier + 'static,
+        <F::Output as WithIdentifier>::ID: serde::Serialize + 'static,
+    {
+        self.registry.record_serialized::<F::Output>(name);
+        self.register::<F>(name)
+    }
+
+    /// Creates a resource with the factory registered by `name`, with the given attributes.
+    /// The resource can be downcast to the `Output` of the factory.
+    pub fn create_dyn(&mut self, name: &str, attributes: DynAttributes<'_>) -> Box<dyn Any> {
+        match self.try_create_dyn(name, attributes) {
+            Ok(resource) => resource,
+            Err(err) => panic!(
+                "Failed to create resource with factory `{name}` : {err}{}",
+                self.seed_hint()
+            ),
+        }
+    }
+
+    pub fn try_create_dyn(
+        &mut self,
+        name: &str,
+        attributes: DynAttributes<'_>,
+    ) -> Result<Box<dyn Any>, DynCreateError<CTX::Error>> {
+        let _seed_hint = SeedHint(self.seed);
+        let (factories, ctx) = (&self.factories, &mut self.ctx);
+        self.registry
+            .record_during(|| factories.create(name, attributes, ctx))
+    }
+
+    /// Creates the fixtures described by a YAML document with the registered factories,
+    /// see [`fixtures`](crate::fixtures)
+    #[cfg(feature = "fixtures")]
+    pub fn load_fixtures_yaml(
+        &mut self,
+        yaml: &str,
+    ) -> Result<crate::fixtures::LoadedFixtures, DynCreateError<CTX::Error>> {
+        self.load_fixtures(crate::fixtures::parse_yaml(yaml)?)
+    }
+
+    /// Creates the fixtures described by a JSON document with the registered factories
+    #[cfg(feature = "fixtures")]
+    pub fn load_fixtures_json(
+        &mut self,
+        json: &str,
+    ) -> Result<crate::fixtures::LoadedFixtures, DynCreateError<CTX::Error>> {
+        self.load_fixtures(crate::fixtures::parse_json(json)?)
+    }
+
+    #[cfg(feature = "fixtures")]
+    fn load_fixtures(
+        &mut self,
+        document: crate::fixtures::Document,
+    ) -> Result<crate::fixtures::LoadedFixtures, DynCreateError<CTX::Error>> {
+        let _seed_hint = SeedHint(self.seed);
+        let (factories, ctx) = (&self.factories, &mut self.ctx);
+        self.registry
+            .record_during(|| crate::fixtures::load(factories, document, ctx))
+    }
+
+    /// Creates the resources of `snapshot` again with the factories registered by the names
+    /// they were recorded under
+    #[cfg(feature = "serde")]
+    pub fn replay(
+        &mut self,
+        snapshot: &crate::snapshot::Snapshot,
+    ) -> Result<(), DynCreateError<CTX::Error>> {
+        let _seed_hint = SeedHint(self.seed);
+        let (factories, ctx) = (&self.factories, &mut self.ctx);
+        self.registry
+            .record_during(|| crate::snapshot::replay(factories, snapshot, ctx))
+    }
+
+    fn seed_hint(&self) -> String {
+        SeedHint(self.seed).to_string()
+    }
+}
+
+/// The seed of a wrapper, printed when creating a resource panics, e.g. in `build_resource`
+struct SeedHint(Option<u64>);
+
+impl std::fmt::Display for SeedHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(seed) => write!(
+                f,
+                " (seed: {seed}, rerun with FABRIKO_SEED={seed} to reproduce)"
             ),
+            None => Ok(()),
         }
     }
+}
+
+impl Drop for SeedHint {
+    fn drop(&mut self) {
+        if self.0.is_some() && std::thread::panicking() {
+            eprintln!("Panicked while creating a resource{self}");
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<CTX: crate::FactoryContextRng, REGISTRY: Recorder> Fabriko<CTX, REGISTRY>
+where
+    CTX::Error: std::fmt::Debug,
+    CTX::Rng: rand::SeedableRng,
+{
+    /// Replaces the random number generator of the context by one seeded with `seed`,
+    /// or with the [`FABRIKO_SEED`](crate::FABRIKO_SEED) environment variable when it is set.
+    ///
+    /// Returns the seed that was used.
+    pub fn reseed(&mut self, seed: u64) -> u64 {
+        let seed = crate::rng::seed_from_env().unwrap_or(seed);
+        *self.ctx.rng() = rand::SeedableRng::seed_from_u64(seed);
+        self.seed = Some(seed);
+        seed
+    }
 
-    pub fn try_bundle<B: FactoryBundle<CTX>>(&mut self) -> Result<B, CTX::Error> {
-        B::create_bundle(&mut self.0)
+    /// Creates a wrapper whose context generates random values from `seed`.
+    ///
+    /// The seed can be overridden with the [`FABRIKO_SEED`](crate::FABRIKO_SEED) environment
+    /// variable, e.g. to reproduce a failure of a wrapper created with a random seed.
+    pub fn with_seed(ctx: CTX, seed: u64) -> Self
+    where
+        REGISTRY: Default,
+    {
+        let mut fabriko = Fabriko {
+            ctx,
+            registry: REGISTRY::default(),
+            seed: None,
+            factories: DynFactories::default(),
+        };
+        fabriko.reseed(seed);
+        fabriko
+    }
+}
+
+impl<CTX: FactoryContext> Fabriko<CTX>
+where
+    CTX::Error: std::fmt::Debug,
+{
+    pub fn new(ctx: CTX) -> Self {
+        Fabriko {
+            ctx,
+            registry: (),
+            seed: None,
+            factories: DynFactories::default(),
+        }
     }
 
     pub fn factory<F: Default, FF: Factory<CTX>, CB: FnOnce(F) -> FF>(
         &mut self,
         define_factory: CB,
     ) -> <FF as Factory<CTX>>::Output {
-        match define_factory(F::default()).create(&mut self.0) {
+        match self.try_factory(define_factory) {
             Ok(resource) => resource,
             Err(err) => {
                 panic!(
-                    "Failed to create resource {} : {}",
+                    "Failed to create resource {} : {}{}",
                     std::any::type_name::<<FF as Factory<CTX>>::Output>(),
-                    err
+                    err.describe(),
+                    self.seed_hint()
                 )
             }
         }
@@ -49,7 +269,152 @@ where
     pub fn try_factory<F: Default, FF: Factory<CTX>, CB: FnOnce(F) -> FF>(
         &mut self,
         define_factory: CB,
-    ) -> Result<<FF as Factory<CTX>>::Output, CTX::Error> {
-        define_factory(F::default()).create(&mut self.0)
+    ) -> Result<<FF as Factory<CTX>>::Output, FabrikoError<CTX::Error>> {
+        let _seed_hint = SeedHint(self.seed);
+        define_factory(F::default()).create(&mut self.ctx)
+    }
+}
+
+impl<CTX: FactoryContext> Fabriko<CTX, Registry>
+where
+    CTX::Error: std::fmt::Debug,
+{
+    pub fn with_registry(ctx: CTX) -> Self {
+        Fabriko {
+            ctx,
+            registry: Registry::default(),
+            seed: None,
+            factories: DynFactories::default(),
+        }
+    }
+
+    /// Creates a wrapper keeping a copy of the created resources, which also records them in a
+    /// [`Snapshot`](crate::snapshot::Snapshot)
+    #[cfg(feature = "serde")]
+    pub fn with_snapshot(ctx: CTX) -> Self {
+        let mut fabriko = Self::with_registry(ctx);
+        fabriko.registry.record_snapshot();
+        fabriko
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Records the resources created from now on in a [`Snapshot`](crate::snapshot::Snapshot)
+    #[cfg(feature = "serde")]
+    pub fn record_snapshot(&mut self) -> &mut Self {
+        self.registry.record_snapshot();
+        self
+    }
+
+    /// The resources recorded in the snapshot so far, in creation order
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> &crate::snapshot::Snapshot {
+        self.registry.snapshot()
+    }
+
+    /// Keeps a copy of the resources of type `T` created from now on, including the ones created
+    /// for the associations of other resources
+    pub fn track<T: Clone + 'static>(&mut self) -> &mut Self {
+        self.registry.track::<T>();
+        self
+    }
+
+    /// All the resources of type `T` created so far, in creation order
+    pub fn created<T: 'static>(&self) -> &[T] {
+        self.registry.created()
+    }
+
+    /// The last resource of type `T` that was created, if any
+    pub fn last<T: 'static>(&self) -> Option<&T> {
+        self.registry.last()
+    }
+
+    pub fn factory<F: Default, FF: Factory<CTX>, CB: FnOnce(F) -> FF>(
+        &mut self,
+        define_factory: CB,
+    ) -> <FF as Factory<CTX>>::Output
+    where
+        <FF as Factory<CTX>>::Output: Clone + 'static,
+    {
+        match self.try_factory(define_factory) {
+            Ok(resource) => resource,
+            Err(err) => {
+                panic!(
+                    "Failed to create resource {} : {}{}",
+                    std::any::type_name::<<FF as Factory<CTX>>::Output>(),
+                    err.describe(),
+                    self.seed_hint()
+                )
+            }
+        }
+    }
+
+    pub fn try_factory<F: Default, FF: Factory<CTX>, CB: FnOnce(F) -> FF>(
+        &mut self,
+        define_factory: CB,
+    ) -> Result<<FF as Factory<CTX>>::Output, FabrikoError<CTX::Error>>
+    where
+        <FF as Factory<CTX>>::Output: Clone + 'static,
+    {
+        let _seed_hint = SeedHint(self.seed);
+        self.registry.track::<FF::Output>();
+        let recorded = self.registry.created::<FF::Output>().len();
+        let ctx = &mut self.ctx;
+        let resource = self
+            .registry
+            .record_during(|| define_factory(F::default()).create(ctx))?;
+        // Factories implemented by hand do not report the resources they create
+        if self.registry.created::<FF::Output>().len() == recorded {
+            self.registry.record(&resource);
+        }
+        Ok(resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    struct TestContext;
+
+    impl FactoryContext for TestContext {
+        type Error = Infallible;
+    }
+
+    fn assert_send_and_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn should_be_send_and_sync_when_the_context_is() {
+        assert_send_and_sync::<Fabriko<TestContext>>();
+    }
+
+    #[derive(Default)]
+    struct PanickingFactory;
+
+    impl Factory<TestContext> for PanickingFactory {
+        type Output = ();
+
+        fn create(self, _ctx: &mut TestContext) -> Result<(), FabrikoError<Infallible>> {
+            panic!("Failed to build");
+        }
+    }
+
+    #[test]
+    fn should_hint_at_the_seed_when_creating_a_resource_panics() {
+        let mut fabriko = Fabriko::new(TestContext);
+        fabriko.seed = Some(42);
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            fabriko.try_factory(|f: PanickingFactory| f)
+        }))
+        .expect_err("The factory panics");
+        assert_eq!(panic.downcast_ref::<&str>(), Some(&"Failed to build"));
+        assert_eq!(
+            SeedHint(Some(42)).to_string(),
+            " (seed: 42, rerun with FABRIKO_SEED=42 to reproduce)"
+        );
     }
 }
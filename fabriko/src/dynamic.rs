@@ -0,0 +1,497 @@
+//! Sets the attributes of a factory by their name, for data-driven tests.
+//!
+//! ```
+//! use fabriko::{dynamic::DynFactory, Fabriko, FactoryContext, WithIdentifier};
+//!
+//! #[derive(Default)]
+//! struct TestContext {
+//!     todos: usize,
+//! }
+//!
+//! impl FactoryContext for TestContext {
+//!     type Error = std::convert::Infallible;
+//! }
+//!
+//! #[derive(Debug, Clone, WithIdentifier)]
+//! struct Todo {
+//!     #[identifier]
+//!     id: usize,
+//!     title: String,
+//! }
+//!
+//! fabriko::factory! {
+//!     #[factory(dynamic)]
+//!     TodoFactory for Todo in TestContext {
+//!         #[factory(into)]
+//!         title: String = "My todo".into(),
+//!     } => |TodoDefinition { title }, ctx| {
+//!         ctx.todos += 1;
+//!         Ok(Todo { id: ctx.todos, title })
+//!     }
+//! }
+//!
+//! let mut f = Fabriko::new(TestContext::default());
+//! let todo = f.factory(|t: TodoFactory| {
+//!     t.set_any("title", Box::new("Do the laundry".to_string()))
+//!         .expect("TodoFactory has a title")
+//! });
+//! assert_eq!(todo.title, "Do the laundry");
+//!
+//! // With the `serde` feature, values can also be given as JSON
+//! # #[cfg(feature = "serde")]
+//! let todo = f.factory(|t: TodoFactory| {
+//!     t.set_value("title", serde_json::json!("Do the laundry"))
+//!         .expect("TodoFactory has a title")
+//! });
+//! # #[cfg(feature = "serde")]
+//! assert_eq!(todo.title, "Do the laundry");
+//!
+//! // Factories can also be registered by name, and create resources whose type is only known
+//! // at runtime
+//! f.register::<TodoFactory>("todo");
+//! let todo = f.create_dyn("todo", vec![("title", Box::new("Do the dishes".to_string()))]);
+//! let todo: Box<Todo> = todo.downcast().expect("The todo factory creates todos");
+//! assert_eq!(todo.title, "Do the dishes");
+//! ```
+//!
+//! [`DynFactory`] is derived with `#[factory(dynamic)]`. Values given as `Box<dyn Any>` have to
+//! be of the exact type of the attribute. Values given as `serde_json::Value`s are deserialized
+//! to the type of the attribute.
+//!
+//! Factories registered by name in [`DynFactories`], e.g. through `Fabriko::register`, are also
+//! used to load fixtures and to replay snapshots.
+
+use std::{any::Any, collections::HashMap, fmt};
+
+use crate::{FabrikoError, Factory, FactoryContext, WithIdentifier};
+
+/// A factory whose attributes can be listed and set by their name
+pub trait DynFactory: Sized {
+    /// The attributes that can be set by their name, in declaration order
+    fn attributes() -> Vec<DynAttribute>;
+
+    /// Sets the attribute named `attribute` to `value`.
+    ///
+    /// A `belongs_to` attribute is set to either the identifier of an existing resource,
+    /// or to the factory creating the resource it belongs to.
+    /// A mixin attribute is replaced by the given mixin.
+    fn set_any(self, attribute: &str, value: Box<dyn Any>) -> Result<Self, DynAttributeError>;
+
+    /// Sets the attribute named `attribute` to `value`, deserialized to the type of the attribute.
+    ///
+    /// A `belongs_to` attribute is set to the identifier of an existing resource.
+    #[cfg(feature = "serde")]
+    fn set_value(
+        self,
+        attribute: &str,
+        value: serde_json::Value,
+    ) -> Result<Self, DynAttributeError> {
+        let value = Self::attributes()
+            .into_iter()
+            .find(|dyn_attribute| dyn_attribute.name == attribute)
+            .ok_or_else(|| DynAttributeError::Unknown(attribute.to_string()))?
+            .deserialize(value)?;
+        self.set_any(attribute, value)
+    }
+}
+
+/// An attribute of a [`DynFactory`]
+#[derive(Debug, Clone, Copy)]
+pub struct DynAttribute {
+    pub name: &'static str,
+    /// The field of the created resource holding the value of the attribute, e.g. the
+    /// `todo_group_id` of a `todo_group` attribute declared with
+    /// `belongs_to(factory = "..", field = "todo_group_id")`. Used to replay snapshots.
+    pub field: &'static str,
+    /// The type of the attribute, i.e. the type of the identifier for a `belongs_to` attribute
+    pub type_name: &'static str,
+    pub kind: DynAttributeKind,
+    /// Reads the value of the attribute, when its type can be deserialized
+    pub deserializer: probe::Deserializer,
+}
+
+impl DynAttribute {
+    /// Whether the attribute can be set with [`DynFactory::set_value`]
+    pub fn is_deserializable(&self) -> bool {
+        self.deserializer.is_some()
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn deserialize(
+        &self,
+        value: serde_json::Value,
+    ) -> Result<Box<dyn Any>, DynAttributeError> {
+        let invalid = |reason: String| DynAttributeError::Invalid {
+            attribute: self.name.to_string(),
+            reason,
+        };
+        let deserialize = self
+            .deserializer
+            .0
+            .ok_or_else(|| invalid(format!("{} can not be deserialized", self.type_name)))?;
+        deserialize(value).map_err(|err| invalid(err.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynAttributeKind {
+    Attribute,
+    BelongsTo,
+    Mixin,
+}
+
+/// The error returned when an attribute of a [`DynFactory`] can not be set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynAttributeError {
+    /// The factory has no such attribute
+    Unknown(String),
+    /// The value is not of the type of the attribute
+    TypeMismatch {
+        attribute: String,
+        expected: &'static str,
+    },
+    /// The value could not be deserialized to the type of the attribute
+    Invalid { attribute: String, reason: String },
+}
+
+impl DynAttributeError {
+    pub fn type_mismatch<T>(attribute: &str) -> Self {
+        DynAttributeError::TypeMismatch {
+            attribute: attribute.to_string(),
+            expected: std::any::type_name::<T>(),
+        }
+    }
+}
+
+impl fmt::Display for DynAttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynAttributeError::Unknown(attribute) => write!(f, "Unknown attribute `{attribute}`"),
+            DynAttributeError::TypeMismatch {
+                attribute,
+                expected,
+            } => write!(
+                f,
+                "Attribute `{attribute}` expects a value of type {expected}"
+            ),
+            DynAttributeError::Invalid { attribute, reason } => {
+                write!(f, "Invalid attribute `{attribute}` : {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DynAttributeError {}
+
+/// Reads the value of an attribute. Used by the code generated by `#[factory(dynamic)]`.
+pub fn downcast_attribute<T: 'static>(
+    attribute: &str,
+    value: Box<dyn Any>,
+) -> Result<T, DynAttributeError> {
+    value
+        .downcast::<T>()
+        .map(|value| *value)
+        .map_err(|_| DynAttributeError::type_mismatch::<T>(attribute))
+}
+
+/// Tells whether the type of an attribute can be deserialized, for the code generated by
+/// `#[factory(dynamic)]` :
+///
+/// ```
+/// use fabriko::dynamic::probe::{Deserializable as _, NotDeserializable as _, Probe};
+/// let deserializer = (&Probe::<String>::new()).deserializer();
+/// ```
+///
+/// `Deserializable` is implemented for `Probe<T>` when `T` can be deserialized, and
+/// `NotDeserializable` for `&Probe<T>`. The method call only falls back to the latter through
+/// auto-referencing, i.e. when `T` can not be deserialized.
+#[doc(hidden)]
+pub mod probe {
+    use std::{any::Any, fmt, marker::PhantomData};
+
+    #[cfg(feature = "serde")]
+    type DeserializeFn = fn(serde_json::Value) -> Result<Box<dyn Any>, serde_json::Error>;
+    #[cfg(not(feature = "serde"))]
+    type DeserializeFn = fn(std::convert::Infallible) -> Box<dyn Any>;
+
+    /// Reads a `serde_json::Value` as the type of an attribute
+    #[derive(Clone, Copy)]
+    pub struct Deserializer(pub(super) Option<DeserializeFn>);
+
+    impl Deserializer {
+        pub(super) fn is_some(&self) -> bool {
+            self.0.is_some()
+        }
+    }
+
+    impl fmt::Debug for Deserializer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.0 {
+                Some(_) => f.write_str("Deserializer"),
+                None => f.write_str("NotDeserializable"),
+            }
+        }
+    }
+
+    pub struct Probe<T>(PhantomData<T>);
+
+    impl<T> Probe<T> {
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            Probe(PhantomData)
+        }
+    }
+
+    pub trait Deserializable {
+        fn deserializer(&self) -> Deserializer;
+    }
+
+    #[cfg(feature = "serde")]
+    impl<T: serde::de::DeserializeOwned + 'static> Deserializable for Probe<T> {
+        fn deserializer(&self) -> Deserializer {
+            Deserializer(Some(|value| {
+                serde_json::from_value::<T>(value).map(|value| Box::new(value) as Box<dyn Any>)
+            }))
+        }
+    }
+
+    pub trait NotDeserializable {
+        fn deserializer(&self) -> Deserializer {
+            Deserializer(None)
+        }
+    }
+
+    impl<T> NotDeserializable for &Probe<T> {}
+}
+
+/// The attributes set on a factory created by name, e.g. `vec![("title", Box::new(title))]`
+pub type DynAttributes<'a> = Vec<(&'a str, Box<dyn Any>)>;
+
+type CreateDynFn<CTX> = fn(
+    &mut CTX,
+    DynAttributes<'_>,
+) -> Result<Box<dyn Any>, DynCreateError<<CTX as FactoryContext>::Error>>;
+
+fn create_dyn<CTX, F>(
+    ctx: &mut CTX,
+    attributes: DynAttributes<'_>,
+) -> Result<Box<dyn Any>, DynCreateError<CTX::Error>>
+where
+    CTX: FactoryContext,
+    F: DynFactory + Factory<CTX> + Default,
+    F::Output: 'static,
+{
+    let factory = attributes
+        .into_iter()
+        .try_fold(F::default(), |factory, (attribute, value)| {
+            factory.set_any(attribute, value)
+        })
+        .map_err(DynCreateError::Attribute)?;
+    let resource = factory.create(ctx).map_err(DynCreateError::Create)?;
+    Ok(Box::new(resource))
+}
+
+/// Extracts the identifier of a resource created by name
+pub(crate) type IdentifyFn = fn(&dyn Any) -> Option<Box<dyn Any>>;
+
+fn identify<T: WithIdentifier + 'static>(resource: &dyn Any) -> Option<Box<dyn Any>>
+where
+    T::ID: 'static,
+{
+    let resource = resource.downcast_ref::<T>()?;
+    Some(Box::new(resource.extract_id()))
+}
+
+/// A factory registered in [`DynFactories`]
+struct Registered<CTX: FactoryContext> {
+    attributes: fn() -> Vec<DynAttribute>,
+    create: CreateDynFn<CTX>,
+    /// The `type_name` of the created resources
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    output: &'static str,
+    /// The type of the identifier of the resources, and how to extract it
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    identify: (&'static str, IdentifyFn),
+}
+
+/// Factories registered by name, creating resources whose type is only known at runtime
+pub struct DynFactories<CTX: FactoryContext> {
+    factories: HashMap<&'static str, Registered<CTX>>,
+}
+
+impl<CTX: FactoryContext> Default for DynFactories<CTX> {
+    fn default() -> Self {
+        DynFactories {
+            factories: HashMap::new(),
+        }
+    }
+}
+
+impl<CTX: FactoryContext> fmt::Debug for DynFactories<CTX> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.factories.keys()).finish()
+    }
+}
+
+impl<CTX: FactoryContext> DynFactories<CTX> {
+    /// Lets `F` create resources by `name`, replacing the factory registered before by that name
+    pub fn register<F>(&mut self, name: &'static str)
+    where
+        F: DynFactory + Factory<CTX> + Default + 'static,
+        F::Output: WithIdentifier + 'static,
+        <F::Output as WithIdentifier>::ID: 'static,
+    {
+        self.factories.insert(
+            name,
+            Registered {
+                attributes: F::attributes,
+                create: create_dyn::<CTX, F>,
+                output: std::any::type_name::<F::Output>(),
+                identify: (
+                    std::any::type_name::<<F::Output as WithIdentifier>::ID>(),
+                    identify::<F::Output>,
+                ),
+            },
+        );
+    }
+
+    /// The names of the registered factories
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.factories.keys().copied()
+    }
+
+    /// The attributes of the factory registered by `name`
+    pub fn attributes(&self, name: &str) -> Option<Vec<DynAttribute>> {
+        self.factories
+            .get(name)
+            .map(|registered| (registered.attributes)())
+    }
+
+    /// Creates a resource with the factory registered by `name`, with the given attributes
+    pub fn create(
+        &self,
+        name: &str,
+        attributes: DynAttributes<'_>,
+        ctx: &mut CTX,
+    ) -> Result<Box<dyn Any>, DynCreateError<CTX::Error>> {
+        let registered = self
+            .factories
+            .get(name)
+            .ok_or_else(|| DynCreateError::UnknownFactory(name.to_string()))?;
+        (registered.create)(ctx, attributes)
+    }
+
+    /// The `type_name` of the resources created by the factory registered by `name`
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn output(&self, name: &str) -> Option<&'static str> {
+        self.factories.get(name).map(|registered| registered.output)
+    }
+
+    /// The type of the identifier of the resources created by the factory registered by `name`,
+    /// i.e. the `type_name` of a `belongs_to` attribute referring to them, and how to extract it
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn identify(&self, name: &str) -> Option<(&'static str, IdentifyFn)> {
+        self.factories
+            .get(name)
+            .map(|registered| registered.identify)
+    }
+}
+
+/// The error returned when a resource fails to be created by the name of its factory
+#[derive(Debug)]
+pub enum DynCreateError<E> {
+    /// No factory is registered by this name
+    UnknownFactory(String),
+    /// An attribute of the factory could not be set
+    Attribute(DynAttributeError),
+    /// The factory failed to create the resource
+    Create(FabrikoError<E>),
+    /// The description of the resources, e.g. a fixture file, is not valid
+    Invalid(String),
+    /// The resource named `name`, e.g. the fixture `pets.rex`, failed to be created
+    Named {
+        name: String,
+        error: Box<DynCreateError<E>>,
+    },
+}
+
+impl<E> DynCreateError<E> {
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn named(name: &str) -> impl FnOnce(Self) -> Self + '_ {
+        move |error| DynCreateError::Named {
+            name: name.to_string(),
+            error: Box::new(error),
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for DynCreateError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynCreateError::UnknownFactory(name) => {
+                write!(f, "No factory is registered as `{name}`")
+            }
+            DynCreateError::Attribute(err) => write!(f, "{err}"),
+            DynCreateError::Create(err) => write!(f, "{}", err.describe()),
+            DynCreateError::Invalid(reason) => write!(f, "{reason}"),
+            DynCreateError::Named { name, error } => {
+                write!(f, "Failed to create {name} : {error}")
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for DynCreateError<E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_report_the_expected_type() {
+        assert_eq!(
+            downcast_attribute::<String>("title", Box::new("Todo".to_string())),
+            Ok("Todo".to_string())
+        );
+        let error = downcast_attribute::<String>("title", Box::new("Todo"))
+            .expect_err("A &str is not a String");
+        assert_eq!(
+            error.to_string(),
+            "Attribute `title` expects a value of type alloc::string::String"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_only_deserialize_deserializable_types() {
+        use probe::{Deserializable as _, NotDeserializable as _, Probe};
+
+        struct NotDeserializable;
+
+        let deserialize = Probe::<String>::new()
+            .deserializer()
+            .0
+            .expect("A String can be deserialized");
+        let value = deserialize(serde_json::json!("Todo")).expect("Failed to deserialize");
+        assert_eq!(value.downcast_ref::<String>(), Some(&"Todo".to_string()));
+        assert!((&Probe::<NotDeserializable>::new())
+            .deserializer()
+            .0
+            .is_none());
+    }
+
+    #[test]
+    fn should_reject_unknown_factories() {
+        struct TestContext;
+
+        impl FactoryContext for TestContext {
+            type Error = std::convert::Infallible;
+        }
+
+        let factories = DynFactories::<TestContext>::default();
+        let error = factories
+            .create("todo", Vec::new(), &mut TestContext)
+            .expect_err("No factory is registered");
+        assert_eq!(error.to_string(), "No factory is registered as `todo`");
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+
+/// The error returned when a resource fails to be created.
+///
+/// On top of the error of the context, it records the path through the factories and their
+/// associations that led to the failure, e.g.
+/// `TodoGroupFactory -> todos[3] -> TodoFactory -> belongs_to todo_group -> TodoGroupFactory`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FabrikoError<E> {
+    error: E,
+    /// From the innermost segment to the outermost one, as segments are added while the error
+    /// propagates
+    path: Vec<PathSegment>,
+}
+
+/// A step through the factories that led to a [`FabrikoError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A factory, e.g. `TodoFactory`
+    Factory(&'static str),
+    /// A bundle, e.g. `MyTestBundle`
+    Bundle(&'static str),
+    /// A field of a bundle or of the related resources of a factory, e.g. `todos`
+    Field(&'static str),
+    /// The position of a factory in a `HasMany` or a tuple of factories, e.g. `[3]`
+    Index(usize),
+    /// The resource a factory belongs to, e.g. `belongs_to todo_group`
+    BelongsTo(&'static str),
+}
+
+impl<E> FabrikoError<E> {
+    pub fn new(error: E) -> Self {
+        FabrikoError {
+            error,
+            path: Vec::new(),
+        }
+    }
+
+    /// Records that the error happened within `segment`
+    pub fn context(mut self, segment: PathSegment) -> Self {
+        self.path.push(segment);
+        self
+    }
+
+    /// The error of the context
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+
+    /// The path that led to the error, from the outermost segment to the innermost one
+    pub fn path(&self) -> impl Iterator<Item = &PathSegment> {
+        self.path.iter().rev()
+    }
+
+    fn write_path(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (position, segment) in self.path().enumerate() {
+            // Indexes are attached to the segment they index, e.g. `todos[3]`
+            if position > 0 && !matches!(segment, PathSegment::Index(_)) {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{segment}")?;
+        }
+        if !self.path.is_empty() {
+            write!(f, " : ")?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: fmt::Debug> FabrikoError<E> {
+    /// Renders the path to the error followed by the `Debug` representation of the error,
+    /// for errors that do not implement `Display`, e.g. `()` or `anyhow::Error`
+    pub fn describe(&self) -> impl fmt::Display + '_ {
+        DescribeError(self)
+    }
+}
+
+struct DescribeError<'a, E>(&'a FabrikoError<E>);
+
+impl<E: fmt::Debug> fmt::Display for DescribeError<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_path(f)?;
+        write!(f, "{:?}", self.0.error)
+    }
+}
+
+impl<E> From<E> for FabrikoError<E> {
+    fn from(error: E) -> Self {
+        FabrikoError::new(error)
+    }
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Factory(name) | PathSegment::Bundle(name) | PathSegment::Field(name) => {
+                write!(f, "{name}")
+            }
+            PathSegment::Index(index) => write!(f, "[{index}]"),
+            PathSegment::BelongsTo(field) => write!(f, "belongs_to {field}"),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for FabrikoError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_path(f)?;
+        write!(f, "{}", self.error)
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for FabrikoError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_display_the_path_to_the_error() {
+        let error = FabrikoError::new("Todo group title can not be empty")
+            .context(PathSegment::Factory("TodoGroupFactory"))
+            .context(PathSegment::BelongsTo("todo_group"))
+            .context(PathSegment::Factory("TodoFactory"))
+            .context(PathSegment::Index(3))
+            .context(PathSegment::Field("todos"))
+            .context(PathSegment::Factory("TodoGroupFactory"));
+        assert_eq!(
+            error.to_string(),
+            "TodoGroupFactory -> todos[3] -> TodoFactory -> belongs_to todo_group -> TodoGroupFactory : Todo group title can not be empty"
+        );
+    }
+
+    #[test]
+    fn should_describe_errors_that_do_not_implement_display() {
+        let error = FabrikoError::new(())
+            .context(PathSegment::Field("alice"))
+            .context(PathSegment::Bundle("MyTestSetup"));
+        assert_eq!(error.describe().to_string(), "MyTestSetup -> alice : ()");
+    }
+}
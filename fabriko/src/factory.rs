@@ -1,4 +1,4 @@
-use crate::tuple_hack::UnitTuple;
+use crate::{tuple_hack::UnitTuple, FabrikoError, PathSegment};
 
 pub trait FactoryContext {
     type Error;
@@ -6,13 +6,13 @@ pub trait FactoryContext {
 
 pub trait Factory<CTX: FactoryContext> {
     type Output;
-    fn create(self, ctx: &mut CTX) -> Result<Self::Output, CTX::Error>;
+    fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>>;
 }
 
 impl<CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for UnitTuple<F> {
     type Output = F::Output;
 
-    fn create(self, ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+    fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         self.0.create(ctx)
     }
 }
@@ -20,20 +20,22 @@ impl<CTX: FactoryContext, F: Factory<CTX>> Factory<CTX> for UnitTuple<F> {
 impl<CTX: FactoryContext, F: Factory<CTX> + std::any::Any> Factory<CTX> for Box<F> {
     type Output = F::Output;
 
-    fn create(self, ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+    fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
         (*self).create(ctx)
     }
 }
 
 macro_rules! impl_factory_tuple {
-    ($($T:ident),*) => {
+    ($($index:tt $T:ident),*) => {
         impl<CTX: FactoryContext, $($T: Factory<CTX>),*> Factory<CTX> for ($($T),*) {
             type Output = ($(<$T as Factory<CTX>>::Output),*);
             #[allow(non_snake_case)]
-            fn create(self, ctx: &mut CTX) -> Result<Self::Output, <CTX as FactoryContext>::Error> {
+            fn create(self, ctx: &mut CTX) -> Result<Self::Output, FabrikoError<CTX::Error>> {
                 let ($($T),*) = self;
                 $(
-                    let $T = $T.create(ctx)?;
+                    let $T = $T
+                        .create(ctx)
+                        .map_err(|err| err.context(PathSegment::Index($index)))?;
                 )*
                 Ok(($($T),*))
             }
@@ -41,19 +43,100 @@ macro_rules! impl_factory_tuple {
     };
 }
 
-impl_factory_tuple!(A, B);
-impl_factory_tuple!(A, B, C);
-impl_factory_tuple!(A, B, C, D);
-impl_factory_tuple!(A, B, C, D, E);
-impl_factory_tuple!(A, B, C, D, E, F);
-impl_factory_tuple!(A, B, C, D, E, F, G);
-impl_factory_tuple!(A, B, C, D, E, F, G, H);
-impl_factory_tuple!(A, B, C, D, E, F, G, H, I);
-impl_factory_tuple!(A, B, C, D, E, F, G, H, I, J);
-impl_factory_tuple!(A, B, C, D, E, F, G, H, I, J, K);
-impl_factory_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+impl_factory_tuple!(0 A, 1 B);
+impl_factory_tuple!(0 A, 1 B, 2 C);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K);
+impl_factory_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K, 11 L);
 
 pub trait BuildResource<CTX: FactoryContext> {
     type Output;
     fn build_resource(self, ctx: &mut CTX) -> Result<Self::Output, CTX::Error>;
 }
+
+/// The value of a `#[factory(required)]` attribute of a derived factory that has not been set yet
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Unset;
+
+/// Implemented by the value of a `#[factory(required)]` attribute of type `T` once it is set.
+///
+/// A derived factory only implements [`Factory`] once all its required attributes are set,
+/// so that forgetting one of them is a compile error rather than a default value.
+///
+/// Setting a required attribute changes the type of the factory, so a factory with required
+/// attributes can not be declared through [`HasMany`](crate::HasMany), nor as the factory of a
+/// `has_many` or `has_one` association : they start from the default factory, whose required
+/// attributes are unset, and expect the same type back.
+///
+/// ```
+/// use fabriko::{Factory, FactoryContext};
+///
+/// struct TestContext;
+/// impl FactoryContext for TestContext {
+///     type Error = ();
+/// }
+///
+/// pub struct User {
+///     email: String,
+///     password: String,
+/// }
+///
+/// #[derive(Factory)]
+/// #[factory(factory = "UserFactory", output = "User")]
+/// pub struct UserDefinition {
+///     #[factory(into, default = "\"alice@test.com\".into()")]
+///     email: String,
+///     #[factory(into, required)]
+///     password: String,
+/// }
+///
+/// let user = UserFactory::default()
+///     .password("hunter2")
+///     .create(&mut TestContext)
+///     .expect("Failed to create user");
+/// assert_eq!(user.password, "hunter2");
+/// ```
+///
+/// Leaving `password` unset does not compile :
+///
+/// ```compile_fail
+/// # use fabriko::{Factory, FactoryContext};
+/// # struct TestContext;
+/// # impl FactoryContext for TestContext {
+/// #     type Error = ();
+/// # }
+/// # pub struct User {
+/// #     email: String,
+/// #     password: String,
+/// # }
+/// # #[derive(Factory)]
+/// # #[factory(factory = "UserFactory", output = "User")]
+/// # pub struct UserDefinition {
+/// #     #[factory(into, default = "\"alice@test.com\".into()")]
+/// #     email: String,
+/// #     #[factory(into, required)]
+/// #     password: String,
+/// # }
+/// // error: a required attribute of type `String` is not set
+/// UserFactory::default().create(&mut TestContext);
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "a required attribute of type `{T}` is not set",
+    label = "the factory is missing a required attribute",
+    note = "the attributes declared with `#[factory(required)]` have to be set before creating the resource"
+)]
+pub trait Required<T> {
+    fn into_value(self) -> T;
+}
+
+impl<T> Required<T> for T {
+    fn into_value(self) -> T {
+        self
+    }
+}
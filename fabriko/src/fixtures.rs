@@ -0,0 +1,530 @@
+//! Creates the resources described by YAML or JSON files, similar to Rails fixtures.
+//!
+//! A fixture file maps sets of fixtures to the named fixtures they contain. Every fixture gives
+//! the name of its factory, along with the attributes to set on it :
+//!
+//! ```yaml
+//! persons:
+//!   alice:
+//!     factory: person
+//!     name: Alice
+//! pets:
+//!   rex:
+//!     factory: pet
+//!     name: Rex
+//!     owner: $persons.alice
+//! ```
+//!
+//! A value such as `$persons.alice` refers to the identifier of another fixture, which is
+//! created first. Other values starting with `$`, e.g. `$5.00`, are left as they are.
+//!
+//! Fixtures are created by the factories registered on the wrapper with `Fabriko::register` :
+//!
+//! ```
+//! use fabriko::{Fabriko, FactoryContext, WithIdentifier};
+//!
+//! #[derive(Default)]
+//! struct TestContext {
+//!     persons: usize,
+//!     pets: usize,
+//! }
+//!
+//! impl FactoryContext for TestContext {
+//!     type Error = std::convert::Infallible;
+//! }
+//!
+//! #[derive(Debug, Clone, WithIdentifier)]
+//! struct Person {
+//!     #[identifier]
+//!     id: usize,
+//!     name: String,
+//! }
+//!
+//! #[derive(Debug, Clone, WithIdentifier)]
+//! struct Pet {
+//!     #[identifier]
+//!     id: usize,
+//!     name: String,
+//!     owner: usize,
+//! }
+//!
+//! fabriko::factory! {
+//!     #[factory(dynamic)]
+//!     PersonFactory for Person in TestContext {
+//!         name: String = "Alice".to_string(),
+//!     } => |PersonDefinition { name }, ctx| {
+//!         ctx.persons += 1;
+//!         Ok(Person { id: ctx.persons, name })
+//!     }
+//! }
+//!
+//! fabriko::factory! {
+//!     #[factory(dynamic)]
+//!     PetFactory for Pet in TestContext {
+//!         name: String = "Rex".to_string(),
+//!         #[factory(belongs_to(factory = "PersonFactory"))]
+//!         owner: usize,
+//!     } => |PetDefinition { name, owner }, ctx| {
+//!         ctx.pets += 1;
+//!         Ok(Pet { id: ctx.pets, name, owner })
+//!     }
+//! }
+//!
+//! let mut f = Fabriko::new(TestContext::default());
+//! f.register::<PersonFactory>("person")
+//!     .register::<PetFactory>("pet");
+//! let loaded = f
+//!     .load_fixtures_yaml(
+//!         r#"
+//! persons:
+//!   alice:
+//!     factory: person
+//!     name: Alice
+//! pets:
+//!   rex:
+//!     factory: pet
+//!     name: Rex
+//!     owner: $persons.alice
+//! "#,
+//!     )
+//!     .expect("Failed to load fixtures");
+//! let rex: &Pet = loaded.get("pets.rex").expect("rex is a pet");
+//! assert_eq!(Some(rex.owner), loaded.id::<usize>("persons.alice"));
+//! ```
+//!
+//! The attributes are set by their name through [`DynFactory::set_value`](crate::dynamic::DynFactory::set_value),
+//! derived with `#[factory(dynamic)]`.
+
+use std::{any::Any, collections::HashMap, fmt, marker::PhantomData};
+
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::Value;
+
+use crate::{
+    dynamic::{DynAttributeError, DynCreateError, DynFactories, IdentifyFn},
+    FactoryContext,
+};
+
+/// The entries of a map, in the order they are declared
+struct Ordered<V>(Vec<(String, V)>);
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for Ordered<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OrderedVisitor<V>(PhantomData<V>);
+
+        impl<'de, V: Deserialize<'de>> Visitor<'de> for OrderedVisitor<V> {
+            type Value = Ordered<V>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Ordered(entries))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedVisitor(PhantomData))
+    }
+}
+
+/// The sets of a fixture file, mapping the name of their fixtures to their attributes
+pub(crate) struct Document(Ordered<Ordered<Ordered<Value>>>);
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ordered::deserialize(deserializer).map(Document)
+    }
+}
+
+pub(crate) fn parse_yaml<E>(yaml: &str) -> Result<Document, DynCreateError<E>> {
+    serde_yaml::from_str(yaml)
+        .map_err(|err| DynCreateError::Invalid(format!("Failed to read fixtures : {err}")))
+}
+
+pub(crate) fn parse_json<E>(json: &str) -> Result<Document, DynCreateError<E>> {
+    serde_json::from_str(json)
+        .map_err(|err| DynCreateError::Invalid(format!("Failed to read fixtures : {err}")))
+}
+
+/// Creates every fixture of `document` with `factories`, in the order they are declared unless
+/// they are referred to by a fixture declared before them
+pub(crate) fn load<CTX: FactoryContext>(
+    factories: &DynFactories<CTX>,
+    Document(Ordered(sets)): Document,
+    ctx: &mut CTX,
+) -> Result<LoadedFixtures, DynCreateError<CTX::Error>> {
+    let mut order = Vec::new();
+    let mut definitions = HashMap::new();
+    for (set, Ordered(fixtures)) in sets {
+        for (name, Ordered(attributes)) in fixtures {
+            let fixture = format!("{set}.{name}");
+            order.push(fixture.clone());
+            definitions.insert(fixture, attributes);
+        }
+    }
+
+    let mut loader = Loader {
+        factories,
+        definitions,
+        loaded: LoadedFixtures::default(),
+        in_progress: Vec::new(),
+    };
+    for fixture in &order {
+        loader.create(fixture, ctx)?;
+    }
+    Ok(loader.loaded)
+}
+
+/// The created fixtures, by name
+#[derive(Debug, Default)]
+pub struct LoadedFixtures {
+    fixtures: HashMap<String, LoadedFixture>,
+}
+
+#[derive(Debug)]
+struct LoadedFixture {
+    resource: Box<dyn Any>,
+    identify: IdentifyFn,
+}
+
+impl LoadedFixtures {
+    /// The fixture named `fixture`, e.g. `pets.rex`, if it is of type `T`
+    pub fn get<T: 'static>(&self, fixture: &str) -> Option<&T> {
+        self.fixtures.get(fixture)?.resource.downcast_ref()
+    }
+
+    /// The identifier of `fixture`, e.g. `pets.rex`
+    pub fn id<ID: 'static>(&self, fixture: &str) -> Option<ID> {
+        self.id_of(fixture)?.downcast().ok().map(|id| *id)
+    }
+
+    fn id_of(&self, fixture: &str) -> Option<Box<dyn Any>> {
+        let LoadedFixture { resource, identify } = self.fixtures.get(fixture)?;
+        identify(resource.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.fixtures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty()
+    }
+}
+
+struct Loader<'a, CTX: FactoryContext> {
+    factories: &'a DynFactories<CTX>,
+    definitions: HashMap<String, Vec<(String, Value)>>,
+    loaded: LoadedFixtures,
+    /// The fixtures being created, waiting for the fixtures they refer to
+    in_progress: Vec<String>,
+}
+
+impl<CTX: FactoryContext> Loader<'_, CTX> {
+    /// Creates `fixture` once, and returns its identifier
+    fn create(
+        &mut self,
+        fixture: &str,
+        ctx: &mut CTX,
+    ) -> Result<Box<dyn Any>, DynCreateError<CTX::Error>> {
+        if let Some(id) = self.loaded.id_of(fixture) {
+            return Ok(id);
+        }
+        let invalid =
+            |reason: String| DynCreateError::named(fixture)(DynCreateError::Invalid(reason));
+        if self.in_progress.iter().any(|created| created == fixture) {
+            return Err(invalid(format!(
+                "cyclic reference : {} -> {fixture}",
+                self.in_progress.join(" -> ")
+            )));
+        }
+        let mut definition = self.definitions[fixture].clone();
+        let factory = definition
+            .iter()
+            .position(|(attribute, _)| attribute == "factory")
+            .map(|index| definition.remove(index).1);
+        let factory = match factory {
+            Some(Value::String(factory)) => factory,
+            _ => return Err(invalid("expected the name of its `factory`".to_string())),
+        };
+        let (Some(dyn_attributes), Some((_, identify))) = (
+            self.factories.attributes(&factory),
+            self.factories.identify(&factory),
+        ) else {
+            return Err(DynCreateError::named(fixture)(
+                DynCreateError::UnknownFactory(factory),
+            ));
+        };
+
+        self.in_progress.push(fixture.to_string());
+        let mut attributes = Vec::new();
+        for (attribute, value) in definition {
+            let value = match reference(&value) {
+                Some(reference) if self.definitions.contains_key(reference) => {
+                    self.create(reference, ctx)?
+                }
+                Some(reference) => {
+                    return Err(invalid(format!("unknown fixture `{reference}`")));
+                }
+                None => dyn_attributes
+                    .iter()
+                    .find(|dyn_attribute| dyn_attribute.name == attribute)
+                    .ok_or_else(|| DynAttributeError::Unknown(attribute.clone()))
+                    .and_then(|dyn_attribute| dyn_attribute.deserialize(value))
+                    .map_err(|err| {
+                        DynCreateError::named(fixture)(DynCreateError::Attribute(err))
+                    })?,
+            };
+            attributes.push((attribute, value));
+        }
+        self.in_progress.pop();
+
+        let (names, values): (Vec<String>, Vec<Box<dyn Any>>) = attributes.into_iter().unzip();
+        let attributes = names.iter().map(String::as_str).zip(values).collect();
+        let resource = self
+            .factories
+            .create(&factory, attributes, ctx)
+            .map_err(DynCreateError::named(fixture))?;
+        self.loaded
+            .fixtures
+            .insert(fixture.to_string(), LoadedFixture { resource, identify });
+        Ok(self
+            .loaded
+            .id_of(fixture)
+            .expect("The fixture is created by the factory identifying it"))
+    }
+}
+
+/// The fixture referred to by `value`, e.g. `$persons.alice`.
+///
+/// Only a `$` followed by the name of a set and of a fixture is a reference, any other value
+/// such as `$5.00` is an attribute of its own.
+fn reference(value: &Value) -> Option<&str> {
+    let reference = value.as_str()?.strip_prefix('$')?;
+    let is_name = |name: &str| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    let (set, fixture) = reference.split_once('.')?;
+    (is_name(set) && is_name(fixture)).then_some(reference)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use crate::{
+        dynamic::{downcast_attribute, DynAttribute, DynAttributeKind, DynFactory},
+        BuildResource, Fabriko, FabrikoError, Factory, WithIdentifier,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct TestContext {
+        persons: Vec<Person>,
+    }
+
+    impl FactoryContext for TestContext {
+        type Error = Infallible;
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Person {
+        id: usize,
+        name: String,
+        manager: Option<usize>,
+    }
+
+    impl WithIdentifier for Person {
+        type ID = usize;
+
+        fn extract_id(&self) -> Self::ID {
+            self.id
+        }
+    }
+
+    #[derive(Default)]
+    struct PersonFactory {
+        name: String,
+        manager: Option<usize>,
+    }
+
+    impl DynFactory for PersonFactory {
+        fn attributes() -> Vec<DynAttribute> {
+            use crate::dynamic::probe::{Deserializable as _, Probe};
+            vec![
+                DynAttribute {
+                    name: "name",
+                    field: "name",
+                    type_name: std::any::type_name::<String>(),
+                    kind: DynAttributeKind::Attribute,
+                    deserializer: Probe::<String>::new().deserializer(),
+                },
+                DynAttribute {
+                    name: "manager",
+                    field: "manager",
+                    type_name: std::any::type_name::<usize>(),
+                    kind: DynAttributeKind::BelongsTo,
+                    deserializer: Probe::<usize>::new().deserializer(),
+                },
+            ]
+        }
+
+        fn set_any(
+            mut self,
+            attribute: &str,
+            value: Box<dyn Any>,
+        ) -> Result<Self, DynAttributeError> {
+            match attribute {
+                "name" => self.name = downcast_attribute(attribute, value)?,
+                "manager" => self.manager = Some(downcast_attribute(attribute, value)?),
+                _ => return Err(DynAttributeError::Unknown(attribute.to_string())),
+            }
+            Ok(self)
+        }
+    }
+
+    impl BuildResource<TestContext> for PersonFactory {
+        type Output = Person;
+
+        fn build_resource(self, ctx: &mut TestContext) -> Result<Person, Infallible> {
+            let person = Person {
+                id: ctx.persons.len() + 1,
+                name: self.name,
+                manager: self.manager,
+            };
+            ctx.persons.push(person.clone());
+            Ok(person)
+        }
+    }
+
+    impl Factory<TestContext> for PersonFactory {
+        type Output = Person;
+
+        fn create(self, ctx: &mut TestContext) -> Result<Person, FabrikoError<Infallible>> {
+            Ok(self.build_resource(ctx)?)
+        }
+    }
+
+    fn fabriko() -> Fabriko<TestContext> {
+        let mut f = Fabriko::new(TestContext::default());
+        f.register::<PersonFactory>("person");
+        f
+    }
+
+    #[test]
+    fn should_create_referenced_fixtures_first() {
+        let yaml = r#"
+persons:
+  bob:
+    factory: person
+    name: Bob
+    manager: $persons.alice
+  alice:
+    factory: person
+    name: Alice
+"#;
+        let mut f = fabriko();
+        let loaded = f.load_fixtures_yaml(yaml).expect("Failed to load fixtures");
+
+        assert_eq!(loaded.id::<usize>("persons.alice"), Some(1));
+        assert_eq!(loaded.id::<usize>("persons.bob"), Some(2));
+        let bob = loaded
+            .get::<Person>("persons.bob")
+            .expect("Bob was created");
+        assert_eq!(bob.manager, Some(1));
+        assert_eq!(f.into_inner().persons.len(), 2);
+    }
+
+    #[test]
+    fn should_create_fixtures_in_declaration_order() {
+        let json = r#"{
+            "persons": {
+                "zoe": { "factory": "person", "name": "Zoe" },
+                "alice": { "factory": "person", "name": "Alice" }
+            }
+        }"#;
+        let loaded = fabriko()
+            .load_fixtures_json(json)
+            .expect("Failed to load fixtures");
+
+        assert_eq!(loaded.id::<usize>("persons.zoe"), Some(1));
+        assert_eq!(loaded.id::<usize>("persons.alice"), Some(2));
+    }
+
+    #[test]
+    fn should_only_treat_fixture_names_as_references() {
+        assert_eq!(
+            reference(&Value::from("$persons.alice")),
+            Some("persons.alice")
+        );
+        for literal in [
+            "$5",
+            "$5.00",
+            "$",
+            "$persons",
+            "$persons.",
+            "$persons.alice!",
+            "persons",
+        ] {
+            assert_eq!(reference(&Value::from(literal)), None, "{literal}");
+        }
+
+        let yaml = r#"
+persons:
+  alice:
+    factory: person
+    name: $5
+"#;
+        let loaded = fabriko()
+            .load_fixtures_yaml(yaml)
+            .expect("Failed to load fixtures");
+        let alice = loaded.get::<Person>("persons.alice");
+        assert_eq!(alice.map(|alice| alice.name.as_str()), Some("$5"));
+    }
+
+    #[test]
+    fn should_reject_cyclic_references() {
+        let json = r#"{
+            "persons": {
+                "alice": { "factory": "person", "manager": "$persons.bob" },
+                "bob": { "factory": "person", "manager": "$persons.alice" }
+            }
+        }"#;
+        let error = fabriko()
+            .load_fixtures_json(json)
+            .expect_err("alice and bob refer to each other");
+        assert_eq!(
+            error.to_string(),
+            "Failed to create persons.alice : cyclic reference : persons.alice -> persons.bob -> persons.alice"
+        );
+    }
+
+    #[test]
+    fn should_report_the_failing_fixture() {
+        let yaml = r#"
+persons:
+  alice:
+    factory: person
+    age: 42
+"#;
+        let error = fabriko()
+            .load_fixtures_yaml(yaml)
+            .expect_err("Persons have no age");
+        assert_eq!(
+            error.to_string(),
+            "Failed to create persons.alice : Unknown attribute `age`"
+        );
+    }
+}
@@ -3,8 +3,21 @@ extern crate fabriko_derive;
 mod associations;
 mod bundle;
 mod context;
+pub mod dynamic;
+mod error;
 mod factory;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "memory")]
+pub mod memory;
 mod mixins;
+mod registry;
+#[cfg(feature = "rand")]
+mod rng;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+#[cfg(feature = "strict")]
+pub mod strict;
 mod tuple_hack;
 
 pub use associations::{
@@ -12,14 +25,31 @@ pub use associations::{
     factory::FactoryWithResources,
     has_many::HasMany,
     has_one::{HasOneCreated, HasOneDefault, HasOneToCreate},
+    strategy::{
+        BelongsToStrategy, CreateStrategy, FindExisting, FindOrCreateStrategy, LastCreated,
+        RememberLastCreated, ReuseLastStrategy,
+    },
     with_identifier::WithIdentifier,
     ResolveDependency, WithRelatedResources,
 };
 pub use bundle::FactoryBundle;
 pub use context::Fabriko;
-pub use fabriko_derive::{Fabriko, Factory, FactoryBundle, Mixin, WithIdentifier};
-pub use factory::{BuildResource, Factory, FactoryContext};
+pub use error::{FabrikoError, PathSegment};
+pub use fabriko_derive::{factory, Fabriko, Factory, FactoryBundle, Mixin, WithIdentifier};
+pub use factory::{BuildResource, Factory, FactoryContext, Required, Unset};
 pub use mixins::WithMixin;
+pub use registry::{record_created, Recorder, Registry};
+#[cfg(feature = "rand")]
+pub use rng::{FactoryContextRng, SeededRng, FABRIKO_SEED};
 pub use tuple_hack::AppendTuple;
 
+// Re-exported for the code generated by `#[factory(fake = "..")]`
+#[cfg(feature = "fake")]
+pub use fake;
+#[cfg(feature = "rand")]
+pub use rand;
+// Re-exported for the code generated by `#[factory(proptest)]`
+#[cfg(feature = "proptest")]
+pub use proptest;
+
 pub type FactorySetter<F, T> = fn(F, T) -> F;
@@ -0,0 +1,227 @@
+//! An in-memory context, storing the created resources by type.
+//!
+//! ```ignore
+//! #[derive(Debug, Clone)]
+//! pub struct Person {
+//!     id: Id<Person>,
+//!     name: String,
+//! }
+//!
+//! #[derive(Factory)]
+//! #[factory(factory = "PersonFactory", output = "Person", memory)]
+//! pub struct PersonDefinition {
+//!     #[factory(into)]
+//!     name: String,
+//! }
+//!
+//! let mut f = Fabriko::new(MemoryStore::default());
+//! let alice = f.factory(|p: PersonFactory| p.name("Alice"));
+//! ```
+//!
+//! With `#[factory(memory)]`, `BuildResource<MemoryStore>` is derived : the resource gets the next
+//! [`Id`] of its type (unless `id` is given), and is inserted in the store.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    convert::Infallible,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::{FactoryContext, LastCreated, RememberLastCreated, WithIdentifier};
+
+/// A context that keeps every resource inserted in it, keyed by the type of the resource,
+/// and hands out auto-incremented identifiers for each type.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    resources: HashMap<TypeId, Box<dyn Any>>,
+    sequences: HashMap<TypeId, u64>,
+    last_created: LastCreated,
+}
+
+impl FactoryContext for MemoryStore {
+    type Error = Infallible;
+}
+
+impl RememberLastCreated for MemoryStore {
+    fn last_created(&mut self) -> &mut LastCreated {
+        &mut self.last_created
+    }
+}
+
+impl MemoryStore {
+    /// The next identifier of the resources of type `T`, starting from 1
+    pub fn next_id<T: 'static>(&mut self) -> Id<T> {
+        let sequence = self.sequences.entry(TypeId::of::<T>()).or_default();
+        *sequence += 1;
+        Id::new(*sequence)
+    }
+
+    /// Stores a copy of `resource`, and gives it back
+    pub fn insert<T: Clone + 'static>(&mut self, resource: T) -> T {
+        self.resources
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::<Vec<T>>::default())
+            .downcast_mut::<Vec<T>>()
+            .expect("MemoryStore entries are keyed by the TypeId of their resources")
+            .push(resource.clone());
+        resource
+    }
+
+    /// All the resources of type `T`, in insertion order
+    pub fn all<T: 'static>(&self) -> &[T] {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .and_then(|resources| resources.downcast_ref::<Vec<T>>())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn count<T: 'static>(&self) -> usize {
+        self.all::<T>().len()
+    }
+
+    /// The last resource of type `T` that was inserted, if any
+    pub fn last<T: 'static>(&self) -> Option<&T> {
+        self.all::<T>().last()
+    }
+
+    /// The first resource of type `T` matching `predicate`
+    pub fn find<T: 'static, P: FnMut(&&T) -> bool>(&self, predicate: P) -> Option<&T> {
+        self.all::<T>().iter().find(predicate)
+    }
+
+    /// Every resource of type `T` matching `predicate`
+    pub fn filter<T: 'static, P: FnMut(&&T) -> bool>(&self, predicate: P) -> Vec<&T> {
+        self.all::<T>().iter().filter(predicate).collect()
+    }
+
+    /// The resource of type `T` identified by `id`
+    pub fn get<T>(&self, id: &T::ID) -> Option<&T>
+    where
+        T: WithIdentifier + 'static,
+        T::ID: PartialEq,
+    {
+        self.find(|resource: &&T| resource.extract_id() == *id)
+    }
+}
+
+/// An identifier handed out by [`MemoryStore::next_id`], typed by the resource it identifies
+pub struct Id<T> {
+    value: u64,
+    resource: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    pub fn new(value: u64) -> Self {
+        Id {
+            value,
+            resource: PhantomData,
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+// Implemented by hand so that `T` does not have to implement these traits as well
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Id({})", self.value)
+    }
+}
+
+impl<T> fmt::Display for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+// Identifiers are represented by their value, e.g. to refer to them in fixture files
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Id<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Id<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(Id::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Person {
+        id: Id<Person>,
+        name: &'static str,
+    }
+
+    impl WithIdentifier for Person {
+        type ID = Id<Person>;
+
+        fn extract_id(&self) -> Self::ID {
+            self.id
+        }
+    }
+
+    #[test]
+    fn should_store_resources_by_type() {
+        let mut store = MemoryStore::default();
+        let id = store.next_id();
+        let alice = store.insert(Person { id, name: "Alice" });
+        let id = store.next_id();
+        let bob = store.insert(Person { id, name: "Bob" });
+        store.insert("Not a person");
+
+        assert_eq!(alice.id.value(), 1);
+        assert_eq!(bob.id.value(), 2);
+        assert_eq!(store.all::<Person>(), &[alice.clone(), bob.clone()]);
+        assert_eq!(store.count::<&str>(), 1);
+        assert_eq!(store.get::<Person>(&bob.id), Some(&bob));
+        assert_eq!(store.find(|p: &&Person| p.name == "Alice"), Some(&alice));
+        assert_eq!(store.last::<Person>(), Some(&bob));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+};
+
+thread_local! {
+    /// The registries of the wrappers creating resources on this thread, the innermost last
+    static RECORDING: RefCell<Vec<Registry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keeps a copy of every resource created through a [`Fabriko`](crate::Fabriko) wrapper,
+/// keyed by the type of the resource.
+///
+/// With the `registry` feature, the resources created by derived factories are kept as well
+/// when they are created for an association, e.g. the parent of a `belongs_to` attribute, as
+/// long as their type is tracked with [`Registry::track`]. Derived factories then require the
+/// resources they create to be `'static`. The wrapper tracks the output of the factories it is
+/// called with.
+///
+/// A factory implementing [`Factory`](crate::Factory) by hand can call [`record_created`] to
+/// report the resources it creates.
+///
+/// With the `serde` feature, the registry can also record the created resources in a
+/// [`Snapshot`](crate::snapshot::Snapshot), see [`Registry::record_snapshot`].
+#[derive(Debug, Default)]
+pub struct Registry {
+    tracked: HashMap<TypeId, Tracked>,
+    #[cfg(feature = "serde")]
+    snapshot: crate::snapshot::SnapshotRecorder,
+}
+
+/// The resources of a tracked type, along with the function keeping a copy of them
+#[derive(Debug)]
+struct Tracked {
+    resources: Box<dyn Any>,
+    record: fn(&mut dyn Any, &dyn Any),
+}
+
+fn record_clone<T: Clone + 'static>(resources: &mut dyn Any, resource: &dyn Any) {
+    if let (Some(resources), Some(resource)) = (
+        resources.downcast_mut::<Vec<T>>(),
+        resource.downcast_ref::<T>(),
+    ) {
+        resources.push(resource.clone());
+    }
+}
+
+impl Registry {
+    /// Keeps a copy of the resources of type `T` created from now on
+    pub fn track<T: Clone + 'static>(&mut self) -> &mut Self {
+        self.tracked
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Tracked {
+                resources: Box::<Vec<T>>::default(),
+                record: record_clone::<T>,
+            });
+        self
+    }
+
+    /// Keeps a copy of `resource` if its type is tracked, and records it in the snapshot if its
+    /// factory is registered as serializable
+    pub fn record<T: 'static>(&mut self, resource: &T) {
+        if let Some(Tracked { resources, record }) = self.tracked.get_mut(&TypeId::of::<T>()) {
+            record(resources.as_mut(), resource);
+        }
+        #[cfg(feature = "serde")]
+        self.snapshot.record(TypeId::of::<T>(), resource);
+    }
+
+    /// All the resources of type `T` created so far, in creation order
+    pub fn created<T: 'static>(&self) -> &[T] {
+        self.tracked
+            .get(&TypeId::of::<T>())
+            .and_then(|tracked| tracked.resources.downcast_ref::<Vec<T>>())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The last resource of type `T` that was created, if any
+    pub fn last<T: 'static>(&self) -> Option<&T> {
+        self.created::<T>().last()
+    }
+
+    /// Records the resources created from now on in a snapshot, when their factory is
+    /// registered with `Fabriko::register_serializable`
+    #[cfg(feature = "serde")]
+    pub fn record_snapshot(&mut self) -> &mut Self {
+        self.snapshot.start();
+        self
+    }
+
+    /// The resources recorded since [`Registry::record_snapshot`], in creation order
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> &crate::snapshot::Snapshot {
+        self.snapshot.snapshot()
+    }
+}
+
+/// What a [`Fabriko`](crate::Fabriko) wrapper does with the resources it creates
+pub trait Recorder {
+    /// Runs `create`, recording the resources created meanwhile on this thread
+    fn record_during<R>(&mut self, create: impl FnOnce() -> R) -> R;
+
+    /// Records the resources of type `T` in a snapshot, under the name of their factory
+    #[cfg(feature = "serde")]
+    fn record_serialized<T>(&mut self, _factory: &'static str)
+    where
+        T: serde::Serialize + crate::WithIdentifier + 'static,
+        T::ID: serde::Serialize,
+    {
+    }
+}
+
+impl Recorder for () {
+    fn record_during<R>(&mut self, create: impl FnOnce() -> R) -> R {
+        create()
+    }
+}
+
+impl Recorder for Registry {
+    /// The registry is moved aside while `create` runs, and only restored once `create` returns
+    /// or panics: it appears empty to the code `create` calls, e.g. to a context reading it.
+    fn record_during<R>(&mut self, create: impl FnOnce() -> R) -> R {
+        struct Recording<'a>(&'a mut Registry);
+
+        impl Drop for Recording<'_> {
+            fn drop(&mut self) {
+                if let Some(registry) = RECORDING.with(|recording| recording.borrow_mut().pop()) {
+                    *self.0 = registry;
+                }
+            }
+        }
+
+        RECORDING.with(|recording| recording.borrow_mut().push(std::mem::take(self)));
+        let _recording = Recording(self);
+        create()
+    }
+
+    #[cfg(feature = "serde")]
+    fn record_serialized<T>(&mut self, factory: &'static str)
+    where
+        T: serde::Serialize + crate::WithIdentifier + 'static,
+        T::ID: serde::Serialize,
+    {
+        self.snapshot.serialize::<T>(factory);
+    }
+}
+
+/// Reports a created resource to the registry of the wrapper creating it, if any.
+///
+/// Called by the factories derived with `#[derive(Factory)]` with every resource they create,
+/// with the `registry` feature.
+pub fn record_created<T: 'static>(resource: &T) {
+    RECORDING.with(|recording| {
+        if let Some(registry) = recording.borrow_mut().last_mut() {
+            registry.record(resource);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_record_tracked_types() {
+        let mut registry = Registry::default();
+        registry.track::<String>();
+        registry.record_during(|| {
+            record_created(&"Alice".to_string());
+            record_created(&42);
+        });
+        record_created(&"Bob".to_string());
+
+        assert_eq!(registry.created::<String>(), &["Alice".to_string()]);
+        assert!(registry.created::<i32>().is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+use crate::FactoryContext;
+
+/// The environment variable that overrides the seed given to [`Fabriko::with_seed`](crate::Fabriko::with_seed)
+pub const FABRIKO_SEED: &str = "FABRIKO_SEED";
+
+/// Implemented by contexts that provide a random number generator to their factories,
+/// e.g. to generate the value of `#[factory(fake = "..")]` fields.
+///
+/// Factories derived with `#[factory(rng)]` can also use it in their `default` and `dependant`
+/// expressions, through the `rng` variable.
+pub trait FactoryContextRng: FactoryContext {
+    type Rng: RngCore;
+    fn rng(&mut self) -> &mut Self::Rng;
+}
+
+/// A random number generator that remembers the seed it was created from,
+/// so that a failing test can be reproduced.
+#[derive(Clone)]
+pub struct SeededRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// Creates a generator from a random seed
+impl Default for SeededRng {
+    fn default() -> Self {
+        SeededRng::new(rand::random())
+    }
+}
+
+impl std::fmt::Debug for SeededRng {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SeededRng")
+            .field("seed", &self.seed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SeedableRng for SeededRng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        SeededRng::new(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        SeededRng::new(state)
+    }
+}
+
+/// Reads the seed from the [`FABRIKO_SEED`] environment variable, if set.
+///
+/// An invalid seed is reported on stderr and ignored, so that the seed given by the caller
+/// is used instead.
+pub(crate) fn seed_from_env() -> Option<u64> {
+    parse_seed(&std::env::var(FABRIKO_SEED).ok()?)
+}
+
+fn parse_seed(seed: &str) -> Option<u64> {
+    match seed.trim().parse() {
+        Ok(seed) => Some(seed),
+        Err(err) => {
+            eprintln!("Ignoring invalid {FABRIKO_SEED} {seed:?} : {err}");
+            None
+        }
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.rng.fill_bytes(dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_ignore_invalid_seeds() {
+        assert_eq!(parse_seed("42"), Some(42));
+        assert_eq!(parse_seed(" 42\n"), Some(42));
+        assert_eq!(parse_seed("forty-two"), None);
+        assert_eq!(parse_seed(""), None);
+    }
+}
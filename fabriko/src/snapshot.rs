@@ -0,0 +1,573 @@
+//! Records the resources created through a [`Fabriko`](crate::Fabriko) wrapper, to debug a
+//! failing test or to seed a development database.
+//!
+//! The snapshot is kept by the [`Registry`](crate::Registry) of the wrapper, along with the
+//! resources it tracks. Only the resources of the factories registered with
+//! `Fabriko::register_serializable` are recorded, under the name they are registered by,
+//! including the ones created for the associations of other resources :
+//!
+//! ```
+//! use fabriko::{snapshot::Snapshot, Fabriko, FactoryContext, WithIdentifier};
+//!
+//! #[derive(Default)]
+//! struct TestContext {
+//!     persons: Vec<Person>,
+//!     pets: Vec<Pet>,
+//! }
+//!
+//! impl FactoryContext for TestContext {
+//!     type Error = std::convert::Infallible;
+//! }
+//!
+//! #[derive(Debug, Clone, serde::Serialize, WithIdentifier)]
+//! struct Person {
+//!     #[identifier]
+//!     id: usize,
+//!     name: String,
+//! }
+//!
+//! #[derive(Debug, Clone, serde::Serialize, WithIdentifier)]
+//! struct Pet {
+//!     #[identifier]
+//!     id: usize,
+//!     name: String,
+//!     owner_id: usize,
+//! }
+//!
+//! fabriko::factory! {
+//!     #[factory(dynamic)]
+//!     PersonFactory for Person in TestContext {
+//!         name: String = "Alice".to_string(),
+//!     } => |PersonDefinition { name }, ctx| {
+//!         let person = Person { id: ctx.persons.len() + 1, name };
+//!         ctx.persons.push(person.clone());
+//!         Ok(person)
+//!     }
+//! }
+//!
+//! fabriko::factory! {
+//!     #[factory(dynamic)]
+//!     PetFactory for Pet in TestContext {
+//!         #[factory(into)]
+//!         name: String = "Rex".into(),
+//!         #[factory(belongs_to(factory = "PersonFactory", field = "owner_id"))]
+//!         owner: usize,
+//!     } => |PetDefinition { name, owner }, ctx| {
+//!         let pet = Pet { id: ctx.pets.len() + 1, name, owner_id: owner };
+//!         ctx.pets.push(pet.clone());
+//!         Ok(pet)
+//!     }
+//! }
+//!
+//! let mut f = Fabriko::with_snapshot(TestContext::default());
+//! f.register_serializable::<PersonFactory>("person")
+//!     .register_serializable::<PetFactory>("pet");
+//! // Records the owner created for the pet, then the pet
+//! f.factory(|p: PetFactory| p.name("Rex"));
+//! let json = f.snapshot().to_json();
+//!
+//! // Later on, in another context
+//! let snapshot = Snapshot::from_json(&json).expect("Failed to read snapshot");
+//! let mut dev = Fabriko::new(TestContext::default());
+//! dev.register::<PersonFactory>("person")
+//!     .register::<PetFactory>("pet");
+//! dev.replay(&snapshot).expect("Failed to replay snapshot");
+//! let dev = dev.into_inner();
+//! assert_eq!(dev.pets[0].owner_id, dev.persons[0].id);
+//! ```
+//!
+//! A recorded resource is replayed by setting the attributes of its factory, through
+//! [`DynFactory::set_value`](crate::dynamic::DynFactory::set_value), to the fields of the
+//! resource of the same name. A `belongs_to` attribute is set to the identifier its parent was
+//! given when replayed, so that children are linked to their replayed parents. Its identifier is
+//! read from the field given by `belongs_to(field = "..")`, when not named after the attribute.
+//! Replaying a resource whose parent is not part of the snapshot fails.
+//!
+//! Every recorded resource also holds the `type_name` of its type, which has to match the output
+//! of the factory replaying it. Snapshots are only read and written as JSON.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    dynamic::{DynAttributeKind, DynCreateError, DynFactories, IdentifyFn},
+    FactoryContext, WithIdentifier,
+};
+
+/// The resources created through a wrapper, in creation order
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    resources: Vec<SnapshotEntry>,
+}
+
+/// A resource of a [`Snapshot`], along with the name of the factory that created it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub factory: String,
+    /// The `type_name` of the resource, checked against the output of the factory replaying it
+    pub type_name: String,
+    /// The identifier of the resource when it was recorded
+    pub id: Value,
+    pub resource: Value,
+}
+
+impl Snapshot {
+    /// Records `resource`, created by the factory registered by the name `factory`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resource` can not be represented as JSON, e.g. a map whose keys are not strings.
+    pub fn record<T: Serialize + WithIdentifier>(&mut self, factory: &str, resource: &T)
+    where
+        T::ID: Serialize,
+    {
+        self.push(factory, std::any::type_name::<T>(), to_values(resource));
+    }
+
+    fn push(&mut self, factory: &str, type_name: &str, values: serde_json::Result<(Value, Value)>) {
+        let (id, resource) =
+            values.unwrap_or_else(|err| panic!("Failed to snapshot resource of {factory} : {err}"));
+        self.resources.push(SnapshotEntry {
+            factory: factory.to_string(),
+            type_name: type_name.to_string(),
+            id,
+            resource,
+        });
+    }
+
+    pub fn entries(&self) -> &[SnapshotEntry] {
+        &self.resources
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A snapshot only holds JSON values")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// The identifier of `resource` and `resource` itself, as JSON values
+fn to_values<T: Serialize + WithIdentifier>(resource: &T) -> serde_json::Result<(Value, Value)>
+where
+    T::ID: Serialize,
+{
+    Ok((
+        serde_json::to_value(resource.extract_id())?,
+        serde_json::to_value(resource)?,
+    ))
+}
+
+type SerializeFn = fn(&dyn Any) -> Option<serde_json::Result<(Value, Value)>>;
+
+/// How the resources of a type are recorded
+#[derive(Debug)]
+struct Serialized {
+    factory: &'static str,
+    type_name: &'static str,
+    serialize: SerializeFn,
+}
+
+fn serialize<T: Serialize + WithIdentifier + 'static>(
+    resource: &dyn Any,
+) -> Option<serde_json::Result<(Value, Value)>>
+where
+    T::ID: Serialize,
+{
+    resource.downcast_ref::<T>().map(to_values)
+}
+
+/// The snapshot of a [`Registry`](crate::Registry), recording the resources of the types
+/// whose factory is registered with `Fabriko::register_serializable`
+#[derive(Debug, Default)]
+pub(crate) struct SnapshotRecorder {
+    snapshot: Snapshot,
+    recording: bool,
+    serializers: HashMap<TypeId, Serialized>,
+}
+
+impl SnapshotRecorder {
+    /// Records the resources created from now on
+    pub(crate) fn start(&mut self) {
+        self.recording = true;
+    }
+
+    pub(crate) fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// Records the resources of type `T` under the name `factory`
+    pub(crate) fn serialize<T: Serialize + WithIdentifier + 'static>(
+        &mut self,
+        factory: &'static str,
+    ) where
+        T::ID: Serialize,
+    {
+        let serialized = Serialized {
+            factory,
+            type_name: std::any::type_name::<T>(),
+            serialize: serialize::<T>,
+        };
+        self.serializers.insert(TypeId::of::<T>(), serialized);
+    }
+
+    /// Records `resource`, of type `type_id`, if it is serialized
+    pub(crate) fn record(&mut self, type_id: TypeId, resource: &dyn Any) {
+        if !self.recording {
+            return;
+        }
+        if let Some(serialized) = self.serializers.get(&type_id) {
+            if let Some(values) = (serialized.serialize)(resource) {
+                self.snapshot
+                    .push(serialized.factory, serialized.type_name, values);
+            }
+        }
+    }
+}
+
+/// The resources created again so far, by the type and the recorded value of their identifier
+#[derive(Default)]
+struct Replayed(HashMap<(&'static str, String), ReplayedResource>);
+
+/// A resource created again, along with the function extracting its identifier
+struct ReplayedResource {
+    resource: Box<dyn Any>,
+    identify: IdentifyFn,
+}
+
+impl Replayed {
+    /// The identifier given to the resource recorded with the identifier `id` of type `id_type`
+    fn id(&self, id_type: &'static str, id: &Value) -> Option<Box<dyn Any>> {
+        let ReplayedResource { resource, identify } = self.0.get(&(id_type, id.to_string()))?;
+        identify(resource.as_ref())
+    }
+}
+
+/// Creates every resource of `snapshot` again with `factories`, in creation order
+pub(crate) fn replay<CTX: FactoryContext>(
+    factories: &DynFactories<CTX>,
+    snapshot: &Snapshot,
+    ctx: &mut CTX,
+) -> Result<(), DynCreateError<CTX::Error>> {
+    let mut replayed = Replayed::default();
+    for (index, entry) in snapshot.entries().iter().enumerate() {
+        replay_entry(factories, entry, &mut replayed, ctx).map_err(DynCreateError::named(
+            &format!("{} #{index}", entry.factory),
+        ))?;
+    }
+    Ok(())
+}
+
+fn replay_entry<CTX: FactoryContext>(
+    factories: &DynFactories<CTX>,
+    SnapshotEntry {
+        factory,
+        type_name,
+        id,
+        resource,
+    }: &SnapshotEntry,
+    replayed: &mut Replayed,
+    ctx: &mut CTX,
+) -> Result<(), DynCreateError<CTX::Error>> {
+    let (Some(dyn_attributes), Some(output), Some((id_type, identify))) = (
+        factories.attributes(factory),
+        factories.output(factory),
+        factories.identify(factory),
+    ) else {
+        return Err(DynCreateError::UnknownFactory(factory.clone()));
+    };
+    if output != type_name {
+        return Err(DynCreateError::Invalid(format!(
+            "expected a resource of type `{output}`, found `{type_name}`"
+        )));
+    }
+    let Value::Object(fields) = resource else {
+        return Err(DynCreateError::Invalid(
+            "expected a resource to be a map of fields".to_string(),
+        ));
+    };
+    let mut attributes = Vec::new();
+    for dyn_attribute in dyn_attributes {
+        let value = fields.get(dyn_attribute.field);
+        if let DynAttributeKind::BelongsTo = dyn_attribute.kind {
+            let value = value.ok_or_else(|| {
+                DynCreateError::Invalid(format!(
+                    "expected the resource to hold the `{}` field of `{}`",
+                    dyn_attribute.field, dyn_attribute.name
+                ))
+            })?;
+            let parent = replayed.id(dyn_attribute.type_name, value).ok_or_else(|| {
+                DynCreateError::Invalid(format!(
+                    "expected the parent {value} of `{}` to be replayed before",
+                    dyn_attribute.name
+                ))
+            })?;
+            attributes.push((dyn_attribute.name, parent));
+            continue;
+        }
+        // The fields that are not attributes of the factory, e.g. identifiers, are skipped
+        if let Some(value) = value.filter(|_| dyn_attribute.is_deserializable()) {
+            let value = dyn_attribute
+                .deserialize(value.clone())
+                .map_err(DynCreateError::Attribute)?;
+            attributes.push((dyn_attribute.name, value));
+        }
+    }
+    let resource = factories.create(factory, attributes, ctx)?;
+    replayed.0.insert(
+        (id_type, id.to_string()),
+        ReplayedResource { resource, identify },
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::Any, convert::Infallible};
+
+    use crate::{
+        dynamic::{
+            downcast_attribute, DynAttribute, DynAttributeError, DynAttributeKind, DynFactory,
+        },
+        record_created, BuildResource, Fabriko, FabrikoError, Factory, Recorder, Registry,
+        WithIdentifier,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct TestContext {
+        persons: Vec<Person>,
+        pets: Vec<Pet>,
+    }
+
+    impl FactoryContext for TestContext {
+        type Error = Infallible;
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        id: usize,
+        name: String,
+    }
+
+    impl WithIdentifier for Person {
+        type ID = usize;
+
+        fn extract_id(&self) -> Self::ID {
+            self.id
+        }
+    }
+
+    #[derive(Default)]
+    struct PersonFactory {
+        name: String,
+    }
+
+    impl DynFactory for PersonFactory {
+        fn attributes() -> Vec<DynAttribute> {
+            use crate::dynamic::probe::{Deserializable as _, Probe};
+            vec![DynAttribute {
+                name: "name",
+                field: "name",
+                type_name: std::any::type_name::<String>(),
+                kind: DynAttributeKind::Attribute,
+                deserializer: Probe::<String>::new().deserializer(),
+            }]
+        }
+
+        fn set_any(
+            mut self,
+            attribute: &str,
+            value: Box<dyn Any>,
+        ) -> Result<Self, DynAttributeError> {
+            match attribute {
+                "name" => self.name = downcast_attribute(attribute, value)?,
+                _ => return Err(DynAttributeError::Unknown(attribute.to_string())),
+            }
+            Ok(self)
+        }
+    }
+
+    impl BuildResource<TestContext> for PersonFactory {
+        type Output = Person;
+
+        fn build_resource(self, ctx: &mut TestContext) -> Result<Person, Infallible> {
+            let person = Person {
+                id: ctx.persons.len() + 1,
+                name: self.name,
+            };
+            ctx.persons.push(person.clone());
+            Ok(person)
+        }
+    }
+
+    impl Factory<TestContext> for PersonFactory {
+        type Output = Person;
+
+        fn create(self, ctx: &mut TestContext) -> Result<Person, FabrikoError<Infallible>> {
+            Ok(self.build_resource(ctx)?)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Pet {
+        id: usize,
+        owner_id: usize,
+    }
+
+    impl WithIdentifier for Pet {
+        type ID = usize;
+
+        fn extract_id(&self) -> Self::ID {
+            self.id
+        }
+    }
+
+    #[derive(Default)]
+    struct PetFactory {
+        owner: usize,
+    }
+
+    impl DynFactory for PetFactory {
+        fn attributes() -> Vec<DynAttribute> {
+            use crate::dynamic::probe::{Deserializable as _, Probe};
+            vec![DynAttribute {
+                name: "owner",
+                field: "owner_id",
+                type_name: std::any::type_name::<usize>(),
+                kind: DynAttributeKind::BelongsTo,
+                deserializer: Probe::<usize>::new().deserializer(),
+            }]
+        }
+
+        fn set_any(
+            mut self,
+            attribute: &str,
+            value: Box<dyn Any>,
+        ) -> Result<Self, DynAttributeError> {
+            match attribute {
+                "owner" => self.owner = downcast_attribute(attribute, value)?,
+                _ => return Err(DynAttributeError::Unknown(attribute.to_string())),
+            }
+            Ok(self)
+        }
+    }
+
+    impl Factory<TestContext> for PetFactory {
+        type Output = Pet;
+
+        fn create(self, ctx: &mut TestContext) -> Result<Pet, FabrikoError<Infallible>> {
+            let pet = Pet {
+                id: ctx.pets.len() + 1,
+                owner_id: self.owner,
+            };
+            ctx.pets.push(pet.clone());
+            Ok(pet)
+        }
+    }
+
+    #[test]
+    fn should_replay_a_snapshot() {
+        let mut snapshot = Snapshot::default();
+        for (id, name) in [(1, "Alice"), (2, "Bob")] {
+            let person = Person {
+                id,
+                name: name.to_string(),
+            };
+            snapshot.record("person", &person);
+        }
+        let snapshot = Snapshot::from_json(&snapshot.to_json()).expect("Failed to read snapshot");
+
+        let mut f = Fabriko::new(TestContext::default());
+        f.register::<PersonFactory>("person");
+        f.replay(&snapshot).expect("Failed to replay snapshot");
+        let names: Vec<String> = f.into_inner().persons.into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["Alice", "Bob"]);
+
+        let error = Fabriko::new(TestContext::default())
+            .replay(&snapshot)
+            .expect_err("No factory is registered as person");
+        assert_eq!(
+            error.to_string(),
+            "Failed to create person #0 : No factory is registered as `person`"
+        );
+    }
+
+    #[test]
+    fn should_record_the_resources_reported_to_the_registry() {
+        let mut registry = Registry::default();
+        registry.record_serialized::<Person>("person");
+        let alice = Person {
+            id: 1,
+            name: "Alice".to_string(),
+        };
+        registry.record_during(|| record_created(&alice));
+        assert!(registry.snapshot().entries().is_empty());
+
+        registry.record_snapshot();
+        registry.record_during(|| {
+            record_created(&alice);
+            record_created(&"Not serialized".to_string());
+        });
+        assert_eq!(
+            registry.snapshot().entries(),
+            &[SnapshotEntry {
+                factory: "person".to_string(),
+                type_name: std::any::type_name::<Person>().to_string(),
+                id: serde_json::json!(1),
+                resource: serde_json::json!({ "id": 1, "name": "Alice" }),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_link_replayed_children_to_their_replayed_parents() {
+        let mut snapshot = Snapshot::default();
+        let alice = Person {
+            id: 7,
+            name: "Alice".to_string(),
+        };
+        snapshot.record("person", &alice);
+        snapshot.record("pet", &Pet { id: 3, owner_id: 7 });
+
+        let mut f = Fabriko::new(TestContext::default());
+        f.register::<PersonFactory>("person");
+        f.register::<PetFactory>("pet");
+        f.replay(&snapshot).expect("Failed to replay snapshot");
+        assert_eq!(f.into_inner().pets, vec![Pet { id: 1, owner_id: 1 }]);
+    }
+
+    #[test]
+    fn should_reject_invalid_snapshots() {
+        let mut f = Fabriko::new(TestContext::default());
+        f.register::<PersonFactory>("person");
+        f.register::<PetFactory>("pet");
+
+        let mut orphan = Snapshot::default();
+        orphan.record("pet", &Pet { id: 3, owner_id: 7 });
+        let error = f.replay(&orphan).expect_err("The owner was not replayed");
+        assert_eq!(
+            error.to_string(),
+            "Failed to create pet #0 : expected the parent 7 of `owner` to be replayed before"
+        );
+
+        let mut mistyped = Snapshot::default();
+        mistyped.record("person", &Pet { id: 3, owner_id: 7 });
+        let error = f.replay(&mistyped).expect_err("A pet is not a person");
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Failed to create person #0 : expected a resource of type `{}`, found `{}`",
+                std::any::type_name::<Person>(),
+                std::any::type_name::<Pet>()
+            )
+        );
+    }
+}
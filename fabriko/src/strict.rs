@@ -0,0 +1,80 @@
+//! Checks of the setters called on derived factories, enabled by the `strict` feature.
+//!
+//! Setting an attribute twice, e.g. `.title("a").title("b")`, silently drops the first value,
+//! and so does setting the identifier of a `belongs_to` attribute after the factory of the
+//! resource it belongs to, e.g. `.belonging_to_todo_group(..).todo_group(id)`.
+//! With `strict`, such calls print a warning, or panic when the [`FABRIKO_STRICT`] environment
+//! variable is set to `panic`, e.g. `FABRIKO_STRICT=panic cargo test`.
+
+/// The environment variable that makes overwritten attributes panic when set to `panic`
+pub const FABRIKO_STRICT: &str = "FABRIKO_STRICT";
+
+/// The attributes explicitly set on a factory, along with the setter that set them
+#[derive(Debug, Default)]
+pub struct SetAttributes {
+    set: Vec<(&'static str, &'static str)>,
+}
+
+impl SetAttributes {
+    /// Records that `attribute` of the factory `F` is set by `setter`,
+    /// reporting that it was already set
+    pub fn record<F>(&mut self, attribute: &'static str, setter: &'static str) {
+        if let Some((_, previous)) = self.set.iter().find(|(set, _)| *set == attribute) {
+            let factory = std::any::type_name::<F>();
+            let message = if *previous == setter {
+                format!("{factory} : `{attribute}` is set twice, dropping its first value")
+            } else {
+                format!(
+                    "{factory} : `{attribute}` is set by `{setter}` after `{previous}`, \
+                     dropping the value set by `{previous}`"
+                )
+            };
+            report(message, std::env::var(FABRIKO_STRICT).ok().as_deref());
+        }
+        self.set.push((attribute, setter));
+    }
+}
+
+/// Panics with `message` when `mode`, read from [`FABRIKO_STRICT`], is `panic`,
+/// prints it as a warning otherwise
+fn report(message: String, mode: Option<&str>) {
+    if mode.is_some_and(|mode| mode.trim() == "panic") {
+        panic!("{message}");
+    } else {
+        eprintln!("warning: {message}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TodoFactory;
+
+    #[test]
+    fn should_accept_distinct_attributes() {
+        let mut set_attributes = SetAttributes::default();
+        set_attributes.record::<TodoFactory>("title", "title");
+        set_attributes.record::<TodoFactory>("todo_group", "todo_group");
+    }
+
+    #[test]
+    fn should_warn_about_overwritten_attributes() {
+        let mut set_attributes = SetAttributes::default();
+        set_attributes.record::<TodoFactory>("todo_group", "belonging_to_todo_group");
+        set_attributes.record::<TodoFactory>("todo_group", "todo_group");
+        assert_eq!(set_attributes.set.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "`todo_group` is set by `todo_group` after `belonging_to_todo_group`"
+    )]
+    fn should_reject_overwritten_attributes_on_demand() {
+        report(
+            "TodoFactory : `todo_group` is set by `todo_group` after `belonging_to_todo_group`"
+                .to_string(),
+            Some("panic"),
+        );
+    }
+}
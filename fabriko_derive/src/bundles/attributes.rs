@@ -0,0 +1,68 @@
+use darling::{ast::NestedMeta, FromMeta};
+use proc_macro2::TokenStream;
+use syn::{Expr, Ident, Meta};
+
+/// The setters called on the factory of a bundle field, in the order they are written, e.g.
+/// `attributes(title = "\"Todo\"", belonging_to_todo_group(title = "\"Group\""))`.
+/// Also used for the defaults of the child factories declared with `#[factory(child(..))]`.
+///
+/// The values are given as they are to the setters, which convert them when the attribute
+/// of the factory is declared with `#[factory(into)]`.
+#[derive(Default)]
+pub(crate) struct BundleAttributes(Vec<(Ident, BundleAttributeValue)>);
+
+enum BundleAttributeValue {
+    /// `setter = "expr"` calls `.setter(expr)`
+    Expr(Expr),
+    /// `setter(..)` calls `.setter(|factory| factory ..)`, customizing the factory of a mixin
+    /// or of an association
+    Nested(BundleAttributes),
+}
+
+impl FromMeta for BundleAttributes {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        let mut errors = darling::Error::accumulator();
+        let attributes = items
+            .iter()
+            .filter_map(|item| errors.handle(parse_attribute(item)))
+            .collect();
+        errors.finish_with(BundleAttributes(attributes))
+    }
+}
+
+fn parse_attribute(item: &NestedMeta) -> darling::Result<(Ident, BundleAttributeValue)> {
+    let meta = match item {
+        NestedMeta::Meta(meta) => meta,
+        NestedMeta::Lit(lit) => return Err(darling::Error::unexpected_lit_type(lit)),
+    };
+    let method = meta
+        .path()
+        .get_ident()
+        .cloned()
+        .ok_or_else(|| darling::Error::custom("Expected a setter").with_span(meta.path()))?;
+    let value = match meta {
+        Meta::List(_) => BundleAttributeValue::Nested(BundleAttributes::from_meta(meta)?),
+        _ => BundleAttributeValue::Expr(Expr::from_meta(meta)?),
+    };
+    Ok((method, value))
+}
+
+impl BundleAttributes {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The calls to the setters, e.g. `.title("Todo").belonging_to_todo_group(|factory| factory.title("Group"))`
+    pub(crate) fn customization(&self) -> TokenStream {
+        self.0
+            .iter()
+            .map(|(method, value)| match value {
+                BundleAttributeValue::Expr(expr) => quote::quote!(.#method(#expr)),
+                BundleAttributeValue::Nested(attributes) => {
+                    let customization = attributes.customization();
+                    quote::quote!(.#method(|factory| factory #customization))
+                }
+            })
+            .collect()
+    }
+}
@@ -1,25 +1,67 @@
-use std::collections::HashMap;
-
 use darling::{
     ast::{Data, Fields},
-    FromDeriveInput, FromField,
+    util::Override,
+    FromDeriveInput, FromField, FromMeta,
 };
 use proc_macro2::TokenStream;
 use syn::{DeriveInput, Expr, Ident, Path, Type};
 
+use self::attributes::BundleAttributes;
+
+pub(crate) mod attributes;
+
 #[derive(FromDeriveInput)]
+#[darling(attributes(bundle))]
 pub(crate) struct BundleDeriveInput {
     ident: Ident,
     data: darling::ast::Data<(), BundleDeriveField>,
+    /// The parameters the bundle is created with, borrowed by the `attributes` expressions
+    /// as `params`. When left unset, the bundle has no parameters.
+    params: Option<Type>,
+    /// The context the bundle is created in. When left unset, the bundle is implemented for
+    /// every context its factories can create their resources in.
+    context: Option<Type>,
 }
 
+/// The fields of a bundle are created in declaration order, each one being bound to a local
+/// variable named after the field. The `attributes` expressions of a field can therefore refer
+/// to the fields declared before it, e.g. `todo_group = "alice_group.id"`.
+///
+/// A field is either created by a `factory`, by the factory that the `create` expression
+/// evaluates to, or is itself a bundle (`#[bundle(bundle)]`).
+/// A bundle can thus extend a base bundle by declaring it as its first field, and refer to it
+/// afterwards, e.g. `todo_group = "base.alice_group.id"`.
 #[derive(FromField)]
 #[darling(attributes(bundle))]
 pub(crate) struct BundleDeriveField {
     ident: Option<Ident>,
     ty: Type,
-    factory: Path,
-    attributes: HashMap<Ident, Expr>,
+    factory: Option<Path>,
+    #[darling(default)]
+    attributes: BundleAttributes,
+    /// Declares the related resources of the resource, e.g. `|related| related.with_todos_times(3)`,
+    /// the field being a tuple of the resource and of its related resources.
+    /// Requires the `context` of the bundle.
+    related: Option<Expr>,
+    /// An expression evaluating to any factory, e.g. `(TodoFactory::default(), UserFactory::default())`.
+    /// Requires the `context` of the bundle.
+    create: Option<Expr>,
+    /// Creates as many resources with the factory, collected in the field, e.g. a `Vec`.
+    /// The position of the resource being created is available as `index`.
+    count: Option<Expr>,
+    /// The field is a bundle itself, e.g. `#[bundle(bundle)]`, or
+    /// `#[bundle(bundle(params = "GroupParams", with = "GroupParams::new(..)"))]`
+    bundle: Option<Override<NestedBundle>>,
+}
+
+/// The options of a field that is a bundle itself
+#[derive(Default, FromMeta)]
+pub(crate) struct NestedBundle {
+    /// The parameters of the nested bundle
+    params: Option<Type>,
+    /// The parameters the nested bundle is created with, requiring `params`. When left unset,
+    /// the nested bundle is created with the default parameters.
+    with: Option<Expr>,
 }
 
 impl BundleDeriveField {
@@ -29,9 +71,74 @@ impl BundleDeriveField {
             ty,
             factory,
             attributes: _,
+            related: _,
+            create: _,
+            count,
+            bundle,
         } = self;
-        quote::quote! {
-            #factory: ::fabriko::Factory<CTX, Output = #ty>,
+        let params = bundle
+            .as_ref()
+            .and_then(|bundle| bundle.as_ref().explicit()?.params.as_ref());
+        match (factory, count) {
+            (Some(factory), None) => quote::quote! {
+                #factory: ::fabriko::Factory<CTX, Output = #ty>,
+            },
+            (Some(factory), Some(_)) => quote::quote! {
+                #factory: ::fabriko::Factory<CTX>,
+                #ty: ::std::iter::FromIterator<<#factory as ::fabriko::Factory<CTX>>::Output>,
+            },
+            // Only allowed along with the `context` of the bundle, which needs no where clause
+            (None, _) if bundle.is_none() => TokenStream::new(),
+            (None, _) if params.is_some() => quote::quote! {
+                #ty: ::fabriko::FactoryBundle<CTX, Params = #params>,
+            },
+            (None, _) => quote::quote! {
+                #ty: ::fabriko::FactoryBundle<CTX>,
+                <#ty as ::fabriko::FactoryBundle<CTX>>::Params: Default,
+            },
+        }
+    }
+
+    /// Checks that the field is either created by a factory or is a bundle, with the options
+    /// that go with it
+    fn validate(&self, has_context: bool) -> darling::Result<()> {
+        let BundleDeriveField {
+            ident,
+            ty: _,
+            factory,
+            attributes,
+            related,
+            create,
+            count,
+            bundle,
+        } = self;
+        let nested_bundle = bundle
+            .as_ref()
+            .and_then(|bundle| bundle.as_ref().explicit());
+        let error = match (factory, create, bundle) {
+            (Some(_), None, None) | (None, Some(_), None) | (None, None, Some(_)) => None,
+            (None, None, None) => Some("Either `factory`, `create` or `bundle` is required"),
+            _ => Some("Only one of `factory`, `create` and `bundle` can be used"),
+        };
+        let error = error.or(match (factory, bundle) {
+            (None, _) if !attributes.is_empty() || related.is_some() => {
+                Some("`attributes` and `related` require a `factory`")
+            }
+            (_, Some(_)) if count.is_some() => Some("`count` can not be used with `bundle`"),
+            _ if nested_bundle
+                .is_some_and(|nested| nested.with.is_some() && nested.params.is_none()) =>
+            {
+                Some("`with` requires the `params` of the nested bundle")
+            }
+            // The type of the factory can not be named in the where clause of the implementation
+            _ if !has_context && (related.is_some() || create.is_some()) => {
+                Some("`related` and `create` require the `context` of the bundle")
+            }
+            _ => None,
+        });
+        match error {
+            Some(error) => Err(darling::Error::custom(error).with_span(ident)),
+            None => Ok(()),
         }
     }
 }
@@ -45,6 +152,10 @@ fn build_bundle_struct(ident: &Ident, fields: &Fields<BundleDeriveField>) -> Tok
                  ty: _,
                  factory: _,
                  attributes: _,
+                 related: _,
+                 create: _,
+                 count: _,
+                 bundle: _,
              }| quote::quote!(#ident,),
         )
         .collect();
@@ -55,24 +166,71 @@ fn build_bundle_struct(ident: &Ident, fields: &Fields<BundleDeriveField>) -> Tok
     )
 }
 
-fn instantiate_bundle_fields(fields: &Fields<BundleDeriveField>) -> TokenStream {
+/// Creates the fields of the bundle in `context`, either `CTX` or the `context` of the bundle
+fn instantiate_bundle_fields(
+    ident: &Ident,
+    context: &TokenStream,
+    fields: &Fields<BundleDeriveField>,
+) -> TokenStream {
+    let bundle_name = ident.to_string();
     fields
         .iter()
         .map(
             |BundleDeriveField {
                  ident,
-                 ty: _,
+                 ty,
                  factory,
                  attributes,
+                 related,
+                 create,
+                 count,
+                 bundle,
              }| {
-                let attributes_customization: TokenStream = attributes
-                    .into_iter()
-                    .map(|(method, expr)| quote::quote!(.#method(#expr)))
-                    .collect();
+                let field_name = ident.as_ref().map(Ident::to_string);
+                let attributes_customization = attributes.customization();
+                let with = bundle
+                    .as_ref()
+                    .and_then(|bundle| bundle.as_ref().explicit()?.with.as_ref());
+                let factory = match (factory, create) {
+                    (Some(factory), _) => {
+                        let factory = quote::quote!(#factory::default() #attributes_customization);
+                        match related {
+                            Some(related) => quote::quote!(
+                                ::fabriko::WithRelatedResources::with_related_resources(
+                                    #factory,
+                                    #related,
+                                )
+                            ),
+                            None => factory,
+                        }
+                    }
+                    (None, Some(create)) => quote::quote!((#create)),
+                    (None, None) => TokenStream::new(),
+                };
+                let created = match (bundle, count, with) {
+                    (None, None, _) => quote::quote!(#factory.create(cx)),
+                    (None, Some(count), _) => quote::quote!(
+                        (0..#count)
+                            .map(|index| {
+                                #factory
+                                .create(cx)
+                                .map_err(|err| err.context(::fabriko::PathSegment::Index(index)))
+                            })
+                            .collect::<Result<#ty, _>>()
+                    ),
+                    (Some(_), _, Some(with)) => quote::quote!(
+                        <#ty as ::fabriko::FactoryBundle<#context>>::create_bundle_with(cx, #with)
+                    ),
+                    (Some(_), _, None) => quote::quote!(
+                        <#ty as ::fabriko::FactoryBundle<#context>>::create_bundle(cx)
+                    ),
+                };
                 quote::quote!(
-                    let #ident = #factory::default()
-                    #attributes_customization
-                    .create(cx)?;
+                    let #ident = #created
+                    .map_err(|err| {
+                        err.context(::fabriko::PathSegment::Field(#field_name))
+                            .context(::fabriko::PathSegment::Bundle(#bundle_name))
+                    })?;
                 )
             },
         )
@@ -81,24 +239,56 @@ fn instantiate_bundle_fields(fields: &Fields<BundleDeriveField>) -> TokenStream
 
 impl BundleDeriveInput {
     pub(crate) fn derive_factory_bundle_implementation(self) -> darling::Result<TokenStream> {
-        let BundleDeriveInput { ident, data } = self;
+        let BundleDeriveInput {
+            ident,
+            data,
+            params,
+            context,
+        } = self;
 
         let fields = match data {
             Data::Enum(_) => unimplemented!(), // TODO: Proper error
             Data::Struct(fields) => fields,
         };
 
-        let where_clause: TokenStream =
-            fields.iter().map(BundleDeriveField::where_clause).collect();
-        let instantiated_bundle_fields = instantiate_bundle_fields(&fields);
+        let mut errors = darling::Error::accumulator();
+        for field in fields.iter() {
+            errors.handle(field.validate(context.is_some()));
+        }
+        errors.finish()?;
+        let params = match params {
+            Some(params) => quote::quote!(#params),
+            None => quote::quote!(()),
+        };
+        // Every factory is known to create its resources in the context of the bundle
+        let (generics, where_clause, context) = match context {
+            Some(context) => (
+                TokenStream::new(),
+                TokenStream::new(),
+                quote::quote!(#context),
+            ),
+            None => (
+                quote::quote!(<CTX: ::fabriko::FactoryContext>),
+                fields.iter().map(BundleDeriveField::where_clause).collect(),
+                quote::quote!(CTX),
+            ),
+        };
+        let instantiated_bundle_fields = instantiate_bundle_fields(&ident, &context, &fields);
         let returned_bundle_struct = build_bundle_struct(&ident, &fields);
 
         Ok(quote::quote! {
-            impl<CTX: ::fabriko::FactoryContext> ::fabriko::FactoryBundle<CTX> for #ident
+            impl #generics ::fabriko::FactoryBundle<#context> for #ident
             where
                 #where_clause
             {
-                fn create_bundle(cx: &mut CTX) -> Result<Self, CTX::Error> {
+                type Params = #params;
+
+                #[allow(unused_variables)]
+                fn create_bundle_with(
+                    cx: &mut #context,
+                    params: Self::Params,
+                ) -> Result<Self, ::fabriko::FabrikoError<<#context as ::fabriko::FactoryContext>::Error>> {
+                    let params = &params;
                     #instantiated_bundle_fields
                     Ok(#returned_bundle_struct)
                 }
@@ -111,3 +301,122 @@ pub(crate) fn do_derive_bundle(input: &DeriveInput) -> darling::Result<TokenStre
     let bundle_derive_input = BundleDeriveInput::from_derive_input(input)?;
     bundle_derive_input.derive_factory_bundle_implementation()
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_instantiate_nested_bundles_and_collections() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct MyBundle {
+                #[bundle(bundle)]
+                base: BaseBundle,
+                #[bundle(
+                    factory = "TodoFactory",
+                    count = "2",
+                    attributes(todo_group = "base.group.id")
+                )]
+                todos: Vec<Todo>,
+            }
+        );
+        let bundle_derive_input =
+            BundleDeriveInput::from_derive_input(&input).expect("Failed to parse bundle");
+        let fields = match bundle_derive_input.data {
+            Data::Enum(_) => unreachable!(),
+            Data::Struct(fields) => fields,
+        };
+
+        let expected = quote::quote!(
+            let base = <BaseBundle as ::fabriko::FactoryBundle<CTX>>::create_bundle(cx)
+                .map_err(|err| {
+                    err.context(::fabriko::PathSegment::Field("base"))
+                        .context(::fabriko::PathSegment::Bundle("MyBundle"))
+                })?;
+            let todos = (0..2)
+                .map(|index| {
+                    TodoFactory::default()
+                        .todo_group(base.group.id)
+                        .create(cx)
+                        .map_err(|err| err.context(::fabriko::PathSegment::Index(index)))
+                })
+                .collect::<Result<Vec<Todo>, _>>()
+                .map_err(|err| {
+                    err.context(::fabriko::PathSegment::Field("todos"))
+                        .context(::fabriko::PathSegment::Bundle("MyBundle"))
+                })?;
+        );
+        let actual =
+            instantiate_bundle_fields(&bundle_derive_input.ident, &quote::quote!(CTX), &fields);
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn should_require_context_to_declare_related_resources() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct MyBundle {
+                #[bundle(
+                    factory = "TodoGroupFactory",
+                    related = "|related| related.with_todos_times(3)"
+                )]
+                busy_group: (TodoGroup, TodoGroupAssociationsOutput<Vec<Todo>>),
+            }
+        );
+        let errors = do_derive_bundle(&input).expect_err("The context of the bundle is unknown");
+        assert!(errors
+            .to_string()
+            .contains("`related` and `create` require the `context` of the bundle"));
+
+        let input: DeriveInput = syn::parse_quote!(
+            #[bundle(context = "TestContext")]
+            struct MyBundle {
+                #[bundle(
+                    factory = "TodoGroupFactory",
+                    related = "|related| related.with_todos_times(3)"
+                )]
+                busy_group: (TodoGroup, TodoGroupAssociationsOutput<Vec<Todo>>),
+            }
+        );
+        assert!(do_derive_bundle(&input).is_ok());
+    }
+
+    #[test]
+    fn should_call_setters_in_declaration_order() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct MyBundle {
+                #[bundle(
+                    factory = "TodoFactory",
+                    attributes(
+                        title = "\"Todo\"",
+                        belonging_to_todo_group(title = "\"Group\"", created_by = "user"),
+                        done = "true",
+                        created_at = "now"
+                    )
+                )]
+                todo: Todo,
+            }
+        );
+        let bundle_derive_input =
+            BundleDeriveInput::from_derive_input(&input).expect("Failed to parse bundle");
+        let fields = match bundle_derive_input.data {
+            Data::Enum(_) => unreachable!(),
+            Data::Struct(fields) => fields,
+        };
+
+        let expected = quote::quote!(
+            let todo = TodoFactory::default()
+                .title("Todo")
+                .belonging_to_todo_group(|factory| factory.title("Group").created_by(user))
+                .done(true)
+                .created_at(now)
+                .create(cx)
+                .map_err(|err| {
+                    err.context(::fabriko::PathSegment::Field("todo"))
+                        .context(::fabriko::PathSegment::Bundle("MyBundle"))
+                })?;
+        );
+        let actual =
+            instantiate_bundle_fields(&bundle_derive_input.ident, &quote::quote!(CTX), &fields);
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+}
@@ -0,0 +1,257 @@
+use proc_macro2::TokenStream;
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, DeriveInput, ExprClosure, Ident, Pat, Token, Type,
+};
+
+use crate::factories::{do_derive_factory_from_definition, DefinitionFieldValue};
+
+/// The input of `factory!` :
+///
+/// ```ignore
+/// fabriko::factory! {
+///     UserFactory for User in TestContext {
+///         #[factory(into)]
+///         name: String = "Alice".into(),
+///         email: String = dependant(format!("{name}@test.com")),
+///     } => |definition, ctx| { .. }
+/// }
+/// ```
+pub(crate) struct FactoryDefinition {
+    attrs: Vec<Attribute>,
+    factory_ident: Ident,
+    output: Type,
+    context: Type,
+    fields: Punctuated<DefinitionField, Token![,]>,
+    build: ExprClosure,
+}
+
+/// A field of the definition, given a default value with `= expr`
+/// or computed from the other fields with `= dependant(expr)`
+struct DefinitionField {
+    attrs: Vec<Attribute>,
+    ident: Ident,
+    ty: Type,
+    value: Option<DefinitionFieldValue>,
+}
+
+impl Parse for FactoryDefinition {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let factory_ident = input.parse()?;
+        input.parse::<Token![for]>()?;
+        let output = input.parse()?;
+        input.parse::<Token![in]>()?;
+        let context = input.parse()?;
+        let content;
+        braced!(content in input);
+        let fields = content.parse_terminated(DefinitionField::parse, Token![,])?;
+        input.parse::<Token![=>]>()?;
+        let build = input.parse()?;
+        Ok(FactoryDefinition {
+            attrs,
+            factory_ident,
+            output,
+            context,
+            fields,
+            build,
+        })
+    }
+}
+
+impl Parse for DefinitionField {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            let fork = input.fork();
+            let is_dependant = fork
+                .parse::<Ident>()
+                .is_ok_and(|ident| ident == "dependant")
+                && fork.peek(syn::token::Paren);
+            if is_dependant {
+                input.parse::<Ident>()?;
+                let content;
+                parenthesized!(content in input);
+                Some(DefinitionFieldValue::Dependant(content.parse()?))
+            } else {
+                Some(DefinitionFieldValue::Default(input.parse()?))
+            }
+        } else {
+            None
+        };
+        Ok(DefinitionField {
+            attrs,
+            ident,
+            ty,
+            value,
+        })
+    }
+}
+
+impl FactoryDefinition {
+    /// The Ident of the generated definition structure,
+    /// e.g. `UserFactory` -> `UserDefinition`
+    fn definition_ident(&self) -> Ident {
+        let factory_name = self.factory_ident.to_string();
+        let name = factory_name
+            .strip_suffix("Factory")
+            .unwrap_or(&factory_name);
+        Ident::new(&format!("{name}Definition"), self.factory_ident.span())
+    }
+
+    fn derive(self) -> darling::Result<TokenStream> {
+        let definition_ident = self.definition_ident();
+        let FactoryDefinition {
+            attrs,
+            factory_ident,
+            output,
+            context,
+            fields,
+            build,
+        } = self;
+
+        let factory_name = factory_ident.to_string();
+        let (fields, values): (Vec<TokenStream>, Vec<Option<DefinitionFieldValue>>) = fields
+            .into_iter()
+            .map(
+                |DefinitionField {
+                     attrs,
+                     ident,
+                     ty,
+                     value,
+                 }| (quote::quote!(#(#attrs)* #ident: #ty,), value),
+            )
+            .unzip();
+        let derive_input: DeriveInput = syn::parse2(quote::quote!(
+            #[factory(factory = #factory_name)]
+            #(#attrs)*
+            pub struct #definition_ident {
+                #(#fields)*
+            }
+        ))?;
+        let factory = do_derive_factory_from_definition(&derive_input, values)?;
+
+        // The `#[factory(..)]` attributes are only meant for the derivation of the factory
+        let definition = strip_factory_attributes(derive_input);
+        let build_resource =
+            derive_build_resource_implementation(&definition_ident, &output, &context, build)?;
+
+        Ok(quote::quote!(
+            #definition
+            #factory
+            #build_resource
+        ))
+    }
+}
+
+fn strip_factory_attributes(mut definition: DeriveInput) -> DeriveInput {
+    let is_not_factory_attribute = |attr: &Attribute| !attr.path().is_ident("factory");
+    definition.attrs.retain(is_not_factory_attribute);
+    if let syn::Data::Struct(data) = &mut definition.data {
+        for field in data.fields.iter_mut() {
+            field.attrs.retain(is_not_factory_attribute);
+        }
+    }
+    definition
+}
+
+/// Inlines the closure given to `factory!` in the implementation of `BuildResource`.
+/// Its parameters are bound to the definition and to the context.
+fn derive_build_resource_implementation(
+    definition_ident: &Ident,
+    output: &Type,
+    context: &Type,
+    build: ExprClosure,
+) -> darling::Result<TokenStream> {
+    let span = build.span();
+    let inputs: Vec<Pat> = build
+        .inputs
+        .into_iter()
+        .map(|input| match input {
+            Pat::Type(pat_type) => *pat_type.pat,
+            pat => pat,
+        })
+        .collect();
+    let [definition_pattern, context_pattern] = <[Pat; 2]>::try_from(inputs).map_err(|_| {
+        darling::Error::custom("Expected a closure taking the definition and the context")
+            .with_span(&span)
+    })?;
+    let body = build.body;
+    Ok(quote::quote!(
+        impl ::fabriko::BuildResource<#context> for #definition_ident {
+            type Output = #output;
+
+            fn build_resource(
+                self,
+                ctx: &mut #context,
+            ) -> Result<Self::Output, <#context as ::fabriko::FactoryContext>::Error> {
+                let #definition_pattern: #definition_ident = self;
+                let #context_pattern: &mut #context = ctx;
+                #body
+            }
+        }
+    ))
+}
+
+pub(crate) fn do_derive_factory_definition(input: TokenStream) -> darling::Result<TokenStream> {
+    let definition: FactoryDefinition = syn::parse2(input)?;
+    definition.derive()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_definition_fields() {
+        let definition: FactoryDefinition = syn::parse_quote!(
+            UserFactory for User in TestContext {
+                #[factory(into)]
+                name: String = "Alice".into(),
+                email: String = dependant(format!("{name}@test.com")),
+                age: u8,
+            } => |definition, ctx| Ok(User::new(definition, ctx))
+        );
+        assert_eq!(definition.definition_ident(), "UserDefinition");
+
+        let fields: Vec<(String, usize, Option<String>)> = definition
+            .fields
+            .into_iter()
+            .map(|field| {
+                let value = field.value.map(|value| match value {
+                    DefinitionFieldValue::Default(expr) => {
+                        format!("default {}", quote::quote!(#expr))
+                    }
+                    DefinitionFieldValue::Dependant(expr) => {
+                        format!("dependant {}", quote::quote!(#expr))
+                    }
+                });
+                (field.ident.to_string(), field.attrs.len(), value)
+            })
+            .collect();
+        let expected_email = quote::quote!(format!("{name}@test.com"));
+        assert_eq!(
+            fields,
+            vec![
+                (
+                    "name".to_string(),
+                    1,
+                    Some(format!("default {}", quote::quote!("Alice".into())))
+                ),
+                (
+                    "email".to_string(),
+                    0,
+                    Some(format!("dependant {expected_email}"))
+                ),
+                ("age".to_string(), 0, None),
+            ]
+        );
+    }
+}
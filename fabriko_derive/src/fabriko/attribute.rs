@@ -9,13 +9,26 @@ pub(crate) struct FabrikoDeriveAttribute {
 }
 
 impl FabrikoDeriveAttribute {
-    pub(crate) fn derive_factory_fn_for_wrapper(self, context_ident: &Ident) -> TokenStream {
+    pub(crate) fn derive_factory_fn_for_wrapper(
+        self,
+        context_ident: &Ident,
+        with_registry: bool,
+    ) -> TokenStream {
         let FabrikoDeriveAttribute { factory, function } = self;
+        // Resources have to be cloned to be kept in the registry
+        let where_clause = with_registry.then(|| {
+            quote::quote!(
+                where
+                    <FF as ::fabriko::Factory<#context_ident>>::Output: Clone + 'static,
+            )
+        });
         quote::quote!(
             pub fn #function<FF: ::fabriko::Factory<#context_ident>, CB: FnOnce(#factory) -> FF>(
                 &mut self,
                 callback: CB,
-            ) -> <FF as ::fabriko::Factory<#context_ident>>::Output {
+            ) -> <FF as ::fabriko::Factory<#context_ident>>::Output
+            #where_clause
+            {
                 self.0.factory(callback)
             }
         )
@@ -45,7 +58,29 @@ pub mod tests {
             }
         );
         let actual = fabriko_derive_attribute
-            .derive_factory_fn_for_wrapper(&Ident::new("MyContext", Span::call_site()));
+            .derive_factory_fn_for_wrapper(&Ident::new("MyContext", Span::call_site()), false);
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn should_derive_factory_fn_for_wrapper_with_registry() {
+        let meta: Meta = syn::parse_quote!(fabriko(factory = "MyFactory", function = "my_factory"));
+        let fabriko_derive_attribute = FabrikoDeriveAttribute::from_meta(&meta)
+            .expect("Failed to parse FabrikoDeriveAttribute");
+
+        let expected = quote::quote!(
+            pub fn my_factory<FF: ::fabriko::Factory<MyContext>, CB: FnOnce(MyFactory) -> FF>(
+                &mut self,
+                callback: CB,
+            ) -> <FF as ::fabriko::Factory<MyContext>>::Output
+            where
+                <FF as ::fabriko::Factory<MyContext>>::Output: Clone + 'static,
+            {
+                self.0.factory(callback)
+            }
+        );
+        let actual = fabriko_derive_attribute
+            .derive_factory_fn_for_wrapper(&Ident::new("MyContext", Span::call_site()), true);
         assert_eq!(expected.to_string(), actual.to_string());
     }
 }
@@ -12,6 +12,13 @@ struct FabrikoDeriveInput {
     factories: Vec<attribute::FabrikoDeriveAttribute>,
     #[darling(rename = "wrapper")]
     fabriko_wrapper_name: Ident,
+    /// When `registry` is set, the wrapper keeps a copy of every created resource
+    #[darling(default, rename = "registry")]
+    with_registry: bool,
+    /// When `rng` is set, the wrapper can be created with a seeded random number generator.
+    /// Requires the `rand` feature of `fabriko`.
+    #[darling(default)]
+    rng: bool,
 }
 
 pub(crate) fn do_derive_fabriko(input: &DeriveInput) -> Result<TokenStream, darling::Error> {
@@ -19,26 +26,105 @@ pub(crate) fn do_derive_fabriko(input: &DeriveInput) -> Result<TokenStream, darl
         ident: context_ident,
         factories,
         fabriko_wrapper_name,
+        with_registry,
+        rng,
     } = FabrikoDeriveInput::from_derive_input(input)?;
 
     let exposed_factories: TokenStream = factories
         .into_iter()
-        .map(|attr| attr.derive_factory_fn_for_wrapper(&context_ident))
+        .map(|attr| attr.derive_factory_fn_for_wrapper(&context_ident, with_registry))
         .collect();
 
+    let (fabriko_ty, constructor, registry_accessors) = if with_registry {
+        (
+            quote::quote!(::fabriko::Fabriko<#context_ident, ::fabriko::Registry>),
+            quote::quote!(::fabriko::Fabriko::with_registry(cx)),
+            quote::quote!(
+                pub fn track<T: Clone + 'static>(&mut self) -> &mut Self {
+                    self.0.track::<T>();
+                    self
+                }
+
+                pub fn created<T: 'static>(&self) -> &[T] {
+                    self.0.created()
+                }
+
+                pub fn last<T: 'static>(&self) -> Option<&T> {
+                    self.0.last()
+                }
+            ),
+        )
+    } else {
+        (
+            quote::quote!(::fabriko::Fabriko<#context_ident>),
+            quote::quote!(::fabriko::Fabriko::new(cx)),
+            TokenStream::new(),
+        )
+    };
+
+    let rng_accessors = rng.then(|| {
+        quote::quote!(
+            pub fn with_seed(cx: #context_ident, seed: u64) -> Self {
+                let mut fabriko = Self::new(cx);
+                fabriko.0.reseed(seed);
+                fabriko
+            }
+
+            pub fn seed(&self) -> Option<u64> {
+                self.0.seed()
+            }
+        )
+    });
+
     Ok(quote::quote!(
         #[derive(Debug, Default)]
-        pub struct #fabriko_wrapper_name(::fabriko::Fabriko<#context_ident>);
+        pub struct #fabriko_wrapper_name(#fabriko_ty);
 
         impl #fabriko_wrapper_name {
             pub fn new(cx: #context_ident) -> Self {
-                #fabriko_wrapper_name(::fabriko::Fabriko::new(cx))
+                #fabriko_wrapper_name(#constructor)
             }
 
-            pub fn bundle<B: ::fabriko::FactoryBundle<#context_ident>>(&mut self) -> B {
+            pub fn bundle<B: ::fabriko::FactoryBundle<#context_ident>>(&mut self) -> B
+            where
+                B::Params: Default,
+            {
                 self.0.bundle()
             }
 
+            pub fn bundle_with<B: ::fabriko::FactoryBundle<#context_ident>>(
+                &mut self,
+                params: B::Params,
+            ) -> B {
+                self.0.bundle_with(params)
+            }
+
+            pub fn register<F>(&mut self, name: &'static str) -> &mut Self
+            where
+                F: ::fabriko::dynamic::DynFactory
+                    + ::fabriko::Factory<#context_ident>
+                    + Default
+                    + 'static,
+                <F as ::fabriko::Factory<#context_ident>>::Output: ::fabriko::WithIdentifier + 'static,
+                <<F as ::fabriko::Factory<#context_ident>>::Output as ::fabriko::WithIdentifier>::ID:
+                    'static,
+            {
+                self.0.register::<F>(name);
+                self
+            }
+
+            pub fn create_dyn(
+                &mut self,
+                name: &str,
+                attributes: ::fabriko::dynamic::DynAttributes<'_>,
+            ) -> Box<dyn ::std::any::Any> {
+                self.0.create_dyn(name, attributes)
+            }
+
+            #registry_accessors
+
+            #rng_accessors
+
             #exposed_factories
         }
     ))
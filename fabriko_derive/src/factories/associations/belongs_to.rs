@@ -1,42 +1,89 @@
-use std::hash::{Hash, Hasher};
-
 use darling::{ast::Fields, FromMeta};
 use proc_macro2::TokenStream;
 use syn::{Ident, Path, Type};
 
 use crate::factories::field::FactoryDeriveField;
+use crate::factories::required::RequiredAttributes;
 
 #[derive(FromMeta)]
 /// TODO: Document
 pub(crate) struct BelongsToAssociation {
-    factory: Path,
+    pub(crate) factory: Path,
+    #[darling(default)]
+    strategy: BelongsToStrategy,
+    /// The field of the created resource holding the identifier, when it is not named after the
+    /// attribute, e.g. `todo_group_id`. Used to replay snapshots of `dynamic` factories.
+    pub(crate) field: Option<String>,
+}
+
+#[derive(Default, FromMeta)]
+/// How the resource is resolved when left unspecified, see `::fabriko::BelongsToStrategy`
+pub(crate) enum BelongsToStrategy {
+    #[default]
+    #[darling(rename = "create")]
+    Create,
+    #[darling(rename = "reuse_last")]
+    ReuseLast,
+    #[darling(rename = "find_or_create")]
+    FindOrCreate,
+}
+
+impl BelongsToStrategy {
+    fn strategy_type(&self) -> TokenStream {
+        match self {
+            BelongsToStrategy::Create => quote::quote!(::fabriko::CreateStrategy),
+            BelongsToStrategy::ReuseLast => quote::quote!(::fabriko::ReuseLastStrategy),
+            BelongsToStrategy::FindOrCreate => quote::quote!(::fabriko::FindOrCreateStrategy),
+        }
+    }
+}
+
+/// The Ident of the zero-sized marker type generated for a `belongs_to` field, used as the
+/// key of its [`BelongingToLink`] implementation, e.g. `TodoFactory__todo_group`.
+pub(crate) fn link_marker_ident(factory_ident: &Ident, field_ident: &Ident) -> Ident {
+    Ident::new(
+        &format!("{factory_ident}__{field_ident}"),
+        field_ident.span(),
+    )
+}
+
+/// The setter customizing the factory of the resource a field belongs to,
+/// e.g. `belonging_to_todo_group`
+pub(crate) fn belonging_to_setter_ident(field_ident: &Ident) -> Ident {
+    Ident::new(&format!("belonging_to_{field_ident}"), field_ident.span())
 }
 
 impl BelongsToAssociation {
     pub(crate) fn derive_belonging_to_link(
         &self,
         factory_ident: &Ident,
+        generics: &TokenStream,
         field_ident: &Option<Ident>,
         field_ty: &Type,
     ) -> TokenStream {
         let ident = field_ident
             .as_ref()
             .expect("Only named structs are supported");
-
-        let mut hasher = fnv::FnvHasher::default();
-        ident.to_string().hash(&mut hasher);
-        let ident_hash = hasher.finish();
+        let link_marker = link_marker_ident(factory_ident, ident);
 
         quote::quote! {
-            impl ::fabriko::BelongingToLink<{ #ident_hash }> for #factory_ident {
+            #[allow(non_camel_case_types)]
+            #[derive(Debug)]
+            pub struct #link_marker;
+
+            impl #generics ::fabriko::BelongingToLink<#link_marker> for #factory_ident #generics {
                 type ID = #field_ty;
-                const SETTER: ::fabriko::FactorySetter<Self, Self::ID> = #factory_ident::#field_ident;
+                const SETTER: ::fabriko::FactorySetter<Self, Self::ID> = Self::#field_ident;
             }
         }
     }
 
     pub(crate) fn field_definition(&self, ident: &Ident, ty: &Type) -> TokenStream {
-        let BelongsToAssociation { factory } = self;
+        let BelongsToAssociation {
+            factory,
+            strategy: _,
+            field: _,
+        } = self;
         quote::quote!(#ident: ::fabriko::BelongsTo<#factory, #ty>,)
     }
 }
@@ -48,8 +95,10 @@ impl BelongsToAssociation {
 /// TODO: Strong-type conditions to WhereClause ?
 pub(crate) fn resolve_belongs_to_assocations_and_add_conditions(
     impl_block_conditions: &mut Vec<TokenStream>,
+    factory_ident: &Ident,
     fields: &Fields<FactoryDeriveField>,
 ) -> TokenStream {
+    let factory_name = factory_ident.to_string();
     fields
         .iter()
         .filter_map(
@@ -61,16 +110,26 @@ pub(crate) fn resolve_belongs_to_assocations_and_add_conditions(
                 into: _,
                 dependant: _,
                 default: _,
+                fake: _,
+                proptest: _,
+                required: _,
+                lazy_default: _,
              }| {
                 let ident = ident.as_ref().unwrap();
+                let field_name = ident.to_string();
                 belongs_to
                     .as_ref()
-                    .map(|BelongsToAssociation { factory }| {
+                    .map(|BelongsToAssociation { factory, strategy, .. }| {
+                        let strategy = strategy.strategy_type();
                         impl_block_conditions.push(
-                        quote::quote! { ::fabriko::BelongsTo<#factory, #ty>: ::fabriko::ResolveDependency<CTX, Output = #ty>, },
+                        quote::quote! { #strategy: ::fabriko::BelongsToStrategy<CTX, #factory, #ty>, },
                     );
                         quote::quote! {
-                            let #ident = #ident.resolve_dependency(ctx)?;
+                            let #ident = <#strategy as ::fabriko::BelongsToStrategy<CTX, #factory, #ty>>::resolve(#ident, ctx)
+                                .map_err(|err| {
+                                    err.context(::fabriko::PathSegment::BelongsTo(#field_name))
+                                        .context(::fabriko::PathSegment::Factory(#factory_name))
+                                })?;
                         }
                     })
             },
@@ -81,23 +140,30 @@ pub(crate) fn resolve_belongs_to_assocations_and_add_conditions(
 pub(crate) fn declare_fields_belonging_to(
     belongs_to_association: &BelongsToAssociation,
     field_ident: &Option<Ident>,
+    field_ty: &Type,
 ) -> TokenStream {
     let ident = field_ident
         .as_ref()
         .expect("Only named structs are supported");
 
-    let mut hasher = fnv::FnvHasher::default();
-    ident.to_string().hash(&mut hasher);
-    let ident_hash = hasher.finish();
-
-    let BelongsToAssociation { factory } = belongs_to_association;
-    let setter_belonging_to = Ident::new(&format!("belonging_to_{}", ident), ident.span());
+    let BelongsToAssociation {
+        factory,
+        strategy: _,
+        field: _,
+    } = belongs_to_association;
+    let setter_belonging_to = belonging_to_setter_ident(ident);
+    let name = ident.to_string();
+    let record_belonging_to =
+        crate::factories::record_set_attribute(&name, &setter_belonging_to.to_string());
+    let record = crate::factories::record_set_attribute(&name, &name);
     quote::quote!(
         pub fn #setter_belonging_to<F: FnOnce(#factory) -> #factory>(mut self, f: F) -> Self {
+            #record_belonging_to
             self.#ident = ::fabriko::BelongsTo::Create(f(Default::default()));
             self
         }
-        pub fn #ident(mut self, id: <Self as ::fabriko::BelongingToLink<{ #ident_hash }>>::ID) -> Self {
+        pub fn #ident(mut self, id: #field_ty) -> Self {
+            #record
             self.#ident = ::fabriko::BelongsTo::Created(id);
             self
         }
@@ -107,7 +173,9 @@ pub(crate) fn declare_fields_belonging_to(
 pub(crate) fn derive_belonging_to_link_implementations(
     factory_ident: &Ident,
     fields: &Fields<FactoryDeriveField>,
+    required: &RequiredAttributes,
 ) -> TokenStream {
+    let generics = required.generics();
     fields
         .iter()
         .filter_map(
@@ -119,9 +187,18 @@ pub(crate) fn derive_belonging_to_link_implementations(
                  dependant: _,
                  belongs_to,
                  default: _,
+                 fake: _,
+                 proptest: _,
+                 required: _,
+                 lazy_default: _,
              }| {
                 belongs_to.as_ref().map(|belongs_to| {
-                    belongs_to.derive_belonging_to_link(factory_ident, field_ident, field_ty)
+                    belongs_to.derive_belonging_to_link(
+                        factory_ident,
+                        &generics,
+                        field_ident,
+                        field_ty,
+                    )
                 })
             },
         )
@@ -1,7 +1,4 @@
-use std::hash::{Hash, Hasher};
-
 use darling::FromMeta;
-use fnv::FnvHasher;
 use proc_macro2::{Span, TokenStream};
 use syn::{Ident, Path};
 
@@ -13,24 +10,19 @@ pub(crate) struct HasManyAssociation {
     #[darling(rename = "factory")]
     pub(crate) for_factory: Path,
     pub(crate) name: Ident,
-    pub(crate) link: Ident,
+    /// Path to the link marker type generated for the `belongs_to` field of the related factory,
+    /// e.g. `TodoFactory__todo_group`
+    pub(crate) link: Path,
 }
 
 impl HasManyAssociation {
     pub(crate) fn has_many_type(&self) -> TokenStream {
-        let setter_hash = self.link_hash();
         let HasManyAssociation {
             for_factory,
             name: _,
-            link: _,
+            link,
         } = self;
-        quote::quote!(::fabriko::HasMany<#setter_hash, #for_factory>)
-    }
-
-    pub(crate) fn link_hash(&self) -> u64 {
-        let mut hasher = FnvHasher::default();
-        self.link.to_string().hash(&mut hasher);
-        hasher.finish()
+        quote::quote!(::fabriko::HasMany<#link, #for_factory>)
     }
 }
 
@@ -45,18 +37,46 @@ impl AssociationsCodegen for HasManyAssociation {
             name,
             link: _,
         } = self;
-        let setter_fn_name = Ident::new(&format!("with_{name}"), Span::call_site());
-        let setter_fn = quote::quote!(#setter_fn_name<FUNC: FnOnce(#for_factory) -> #for_factory>);
-        let setter_hash = self.link_hash();
 
-        AssociationsSetter {
+        let setter_fn_name = Ident::new(&format!("with_{name}"), Span::call_site());
+        let with_setter = AssociationsSetter {
             field_ident: name,
-            setter_fn,
+            setter_fn: quote::quote!(#setter_fn_name<FUNC: FnOnce(#for_factory) -> #for_factory>),
             argument_of_setter: quote::quote!(__func: FUNC),
-            create_set_type_of_association: quote::quote!(#name.with::<#setter_hash, _>(__func)),
+            create_set_type_of_association: quote::quote!(#name.with(__func)),
+            default_type_of_association: has_many_type.clone(),
+            set_type_of_association: has_many_type.clone(),
+        }
+        .derive_setter(structure);
+
+        let times_fn_name = Ident::new(&format!("with_{name}_times"), Span::call_site());
+        let times_setter = AssociationsSetter {
+            field_ident: name,
+            setter_fn: quote::quote!(#times_fn_name),
+            argument_of_setter: quote::quote!(__count: usize),
+            create_set_type_of_association: quote::quote!(#name.times(__count)),
+            default_type_of_association: has_many_type.clone(),
+            set_type_of_association: has_many_type.clone(),
+        }
+        .derive_setter(structure);
+
+        let times_with_fn_name = Ident::new(&format!("with_{name}_times_with"), Span::call_site());
+        let times_with_setter = AssociationsSetter {
+            field_ident: name,
+            setter_fn: quote::quote!(
+                #times_with_fn_name<FUNC: FnMut(usize, #for_factory) -> #for_factory>
+            ),
+            argument_of_setter: quote::quote!(__count: usize, __func: FUNC),
+            create_set_type_of_association: quote::quote!(#name.times_with(__count, __func)),
             default_type_of_association: has_many_type.clone(),
             set_type_of_association: has_many_type,
         }
-        .derive_setter(structure)
+        .derive_setter(structure);
+
+        quote::quote! {
+            #with_setter
+            #times_setter
+            #times_with_setter
+        }
     }
 }
@@ -1,7 +1,5 @@
 use darling::FromMeta;
-use fnv::FnvHasher;
 use proc_macro2::{Span, TokenStream};
-use std::hash::{Hash, Hasher};
 use syn::{Ident, Path};
 
 use crate::factories::associations::AssociationsSetter;
@@ -14,7 +12,9 @@ pub(crate) struct HasOneAssociation {
     #[darling(rename = "factory")]
     pub(crate) for_factory: Path,
     pub(crate) name: Ident,
-    pub(crate) link: Ident,
+    /// Path to the link marker type generated for the `belongs_to` field of the related factory,
+    /// e.g. `CityFactory__country`
+    pub(crate) link: Path,
 }
 
 impl AssociationsCodegen for HasOneAssociation {
@@ -35,15 +35,12 @@ impl<'a> AssociationsCodegen for PredefinedSetter<'a> {
         let HasOneAssociation {
             for_factory,
             name,
-            link: setter,
+            link,
         } = self.0;
         let setter_fn_name = Ident::new(&format!("{name}_id"), Span::call_site());
         let setter_fn = quote::quote!(#setter_fn_name);
-        let mut hasher = FnvHasher::default();
-        setter.to_string().hash(&mut hasher);
-        let setter_hash = hasher.finish();
         let argument_for_setter =
-            quote::quote!(<#for_factory as ::fabriko::BelongingToLink<#setter_hash>>::ID);
+            quote::quote!(<#for_factory as ::fabriko::BelongingToLink<#link>>::ID);
         AssociationsSetter {
             field_ident: name,
             setter_fn,
@@ -68,9 +65,6 @@ impl<'a> AssociationsCodegen for FactorySetter<'a> {
             link,
         } = self.0;
         let setter_fn = quote::quote!(#name<FUNC: FnOnce(#for_factory) -> #for_factory>);
-        let mut hasher = FnvHasher::default();
-        link.to_string().hash(&mut hasher);
-        let setter_hash = hasher.finish();
 
         AssociationsSetter {
             field_ident: name,
@@ -80,7 +74,7 @@ impl<'a> AssociationsCodegen for FactorySetter<'a> {
                 Default::default()
             ))),
             default_type_of_association: quote::quote!(::fabriko::HasOneDefault<#for_factory>),
-            set_type_of_association: quote::quote!(::fabriko::HasOneToCreate<#setter_hash, #for_factory>),
+            set_type_of_association: quote::quote!(::fabriko::HasOneToCreate<#link, #for_factory>),
         }
         .derive_setter(structure)
     }
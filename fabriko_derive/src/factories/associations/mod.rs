@@ -2,6 +2,7 @@ use proc_macro2::{Span, TokenStream};
 use syn::Ident;
 
 use self::{has_many::HasManyAssociation, has_one::HasOneAssociation};
+use super::required::RequiredAttributes;
 
 pub(crate) mod belongs_to;
 pub(crate) mod has_many;
@@ -15,7 +16,7 @@ pub(crate) mod has_one;
 /// * #[factory(has_many( .. ))] -> declares that the derived factory has many such resources
 /// * #[factory(has_one( .. ))] -> declares that the derived factory has exactly one such resources
 /// * #[factory(associations = " .. ")] -> the Ident of struct that will contain the
-/// related resources
+///   related resources
 pub(crate) struct AssociationsDeriveAttributes<'a> {
     has_many: &'a [HasManyAssociation],
     has_one: &'a [HasOneAssociation],
@@ -27,13 +28,14 @@ pub(crate) fn derive_associations(
     has_one: &[HasOneAssociation],
     associations_ty: &Ident,
     factory_ident: &Ident,
+    required: &RequiredAttributes,
 ) -> TokenStream {
     AssociationsDeriveAttributes {
         has_many,
         has_one,
         associations_ty,
     }
-    .derive_related_resources(factory_ident)
+    .derive_related_resources(factory_ident, &required.generics())
 }
 
 /// The supported associations annotations
@@ -91,30 +93,30 @@ impl<'a> AssociationsSetter<'a> {
             .collect();
         let base_generics: TokenStream = fields
             .iter()
-            .filter_map(|field| {
+            .map(|field| {
                 let AssociationAttributesStructureField {
                     field_ident: ident,
                     generic,
                     kind: _,
                 } = field;
                 if ident == field_ident {
-                    return Some(quote::quote!(#default_type_of_association,));
+                    return quote::quote!(#default_type_of_association,);
                 }
-                Some(quote::quote!(#generic,))
+                quote::quote!(#generic,)
             })
             .collect();
         let set_generics: TokenStream = fields
             .iter()
-            .filter_map(|field| {
+            .map(|field| {
                 let AssociationAttributesStructureField {
                     field_ident: ident,
                     generic,
                     kind: _,
                 } = field;
                 if ident == field_ident {
-                    return Some(quote::quote!(#set_type_of_association,));
+                    return quote::quote!(#set_type_of_association,);
                 }
-                Some(quote::quote!(#generic,))
+                quote::quote!(#generic,)
             })
             .collect();
 
@@ -175,8 +177,8 @@ pub(crate) struct AssociationAttributesStructure<'a> {
 // * index = 0 -> 'A' (as an ident)
 // * index = 1 -> 'B' (as an ident)
 // * .. and so on
-fn index_as_generic_char(index: usize) -> Ident {
-    let generic_char_index = 'A' as u8 as usize + index;
+pub(crate) fn index_as_generic_char(index: usize) -> Ident {
+    let generic_char_index = b'A' as usize + index;
     Ident::new(
         &char::from_u32(
             generic_char_index
@@ -208,7 +210,7 @@ impl<'a> AssociationsDeriveAttributes<'a> {
                     AssociationKind::HasMany(many) => &many.name,
                 };
                 AssociationAttributesStructureField {
-                    field_ident: &name,
+                    field_ident: name,
                     generic: index_as_generic_char(index),
                     kind,
                 }
@@ -225,16 +227,22 @@ impl<'a> AssociationsDeriveAttributes<'a> {
 impl<'a> AssociationsDeriveAttributes<'a> {
     /// Derives all the code that will make it possible for a Factory to declare related resources
     /// that directly depends on this resource
-    pub(crate) fn derive_related_resources(&self, factory_ident: &Ident) -> TokenStream {
+    /// `factory_generics` are the generic parameters of the factory, tracking its required
+    /// attributes : the related resources can be declared whether they are set or not.
+    pub(crate) fn derive_related_resources(
+        &self,
+        factory_ident: &Ident,
+        factory_generics: &TokenStream,
+    ) -> TokenStream {
         let association_attributes_structure = self.as_structure();
 
         let structure_decl = association_attributes_structure.derive_structure_declaration();
-        let with_related_resources_impl =
-            association_attributes_structure.derive_with_related_resources_impl(factory_ident);
+        let with_related_resources_impl = association_attributes_structure
+            .derive_with_related_resources_impl(factory_ident, factory_generics);
         let belonging_to_impl =
             association_attributes_structure.derive_belonging_to_implementation_for_associations();
         let setters = association_attributes_structure.derive_setters();
-        let factory_impl = association_attributes_structure.derive_factory_impl();
+        let factory_impl = association_attributes_structure.derive_factory_impl(factory_ident);
 
         quote::quote!(
             #structure_decl
@@ -247,7 +255,12 @@ impl<'a> AssociationsDeriveAttributes<'a> {
 }
 
 impl<'a> AssociationAttributesStructure<'a> {
-    /// TODO: tests
+    /// The Ident of the structure holding the created related resources,
+    /// e.g. `CountryFactoryAssociations` -> `CountryFactoryAssociationsOutput`
+    fn output_ident(&self) -> Ident {
+        Ident::new(&format!("{}Output", self.ident), self.ident.span())
+    }
+
     fn derive_structure_declaration(&self) -> TokenStream {
         let AssociationAttributesStructure { ident, fields } = self;
         let all_generics: TokenStream = fields
@@ -270,16 +283,26 @@ impl<'a> AssociationAttributesStructure<'a> {
                  }| quote::quote!(pub #field_ident: #generic, ),
             )
             .collect();
+        let output_ident = self.output_ident();
         quote::quote!(
             #[derive(Default)]
             pub struct #ident<#all_generics> {
                 #all_fields
             }
+
+            #[derive(Debug)]
+            pub struct #output_ident<#all_generics> {
+                #all_fields
+            }
         )
     }
 
     /// TODO: tests
-    fn derive_with_related_resources_impl(&self, factory_ident: &Ident) -> TokenStream {
+    fn derive_with_related_resources_impl(
+        &self,
+        factory_ident: &Ident,
+        factory_generics: &TokenStream,
+    ) -> TokenStream {
         let AssociationAttributesStructure { ident, fields } = self;
         let generics_of_associations_type: TokenStream = fields
             .iter()
@@ -302,7 +325,9 @@ impl<'a> AssociationAttributesStructure<'a> {
             )
             .collect();
         quote::quote!(
-            impl ::fabriko::WithRelatedResources for #factory_ident {
+            impl #factory_generics ::fabriko::WithRelatedResources
+                for #factory_ident #factory_generics
+            {
                 type DefaultAssociations = #ident<#generics_of_associations_type>;
             }
         )
@@ -370,8 +395,10 @@ impl<'a> AssociationAttributesStructure<'a> {
     }
 
     /// TODO: tests
-    fn derive_factory_impl(&self) -> TokenStream {
+    fn derive_factory_impl(&self, factory_ident: &Ident) -> TokenStream {
         let AssociationAttributesStructure { ident, fields } = self;
+        // Errors of the related resources are reported within the factory they are related to
+        let factory_name = factory_ident.to_string();
         let generics_with_factory_constraint: TokenStream = fields
             .iter()
             .map(|field| {
@@ -424,24 +451,31 @@ impl<'a> AssociationAttributesStructure<'a> {
                     generic: _,
                     kind: _,
                 } = field;
-                quote::quote!(let #field_ident = #field_ident.create(ctx)?;)
+                let field_name = field_ident.to_string();
+                quote::quote!(
+                    let #field_ident = #field_ident.create(ctx).map_err(|err| {
+                        err.context(::fabriko::PathSegment::Field(#field_name))
+                            .context(::fabriko::PathSegment::Factory(#factory_name))
+                    })?;
+                )
             })
             .collect();
+        let output_ident = self.output_ident();
         quote::quote!(
             impl<CTX: ::fabriko::FactoryContext, #generics_with_factory_constraint> ::fabriko::Factory<CTX>
                 for #ident<#generics>
             {
-                type Output = #ident<#generics_output>;
+                type Output = #output_ident<#generics_output>;
 
                 fn create(
                     self,
                     ctx: &mut CTX,
-                ) -> Result<Self::Output, <CTX as ::fabriko::FactoryContext>::Error> {
+                ) -> Result<Self::Output, ::fabriko::FabrikoError<CTX::Error>> {
                     let #ident {
                         #fields_idents
                     } = self;
                     #fields_reassign
-                    Ok(#ident {
+                    Ok(#output_ident {
                         #fields_idents
                     })
                 }
@@ -449,3 +483,43 @@ impl<'a> AssociationAttributesStructure<'a> {
         )
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use darling::FromMeta;
+    use syn::Meta;
+
+    use super::*;
+
+    #[test]
+    fn should_derive_structure_declaration_with_output() {
+        let meta: Meta = syn::parse_quote!(has_many(
+            factory = "TodoFactory",
+            name = "todos",
+            link = "TodoFactory__todo_group"
+        ));
+        let has_many = [HasManyAssociation::from_meta(&meta).expect("Failed to parse has_many")];
+        let associations_ty: Ident = syn::parse_quote!(TodoGroupAssociations);
+        let structure = AssociationsDeriveAttributes {
+            has_many: &has_many,
+            has_one: &[],
+            associations_ty: &associations_ty,
+        }
+        .as_structure();
+
+        let generics = quote::quote!(A,);
+        let expected = quote::quote!(
+            #[derive(Default)]
+            pub struct TodoGroupAssociations<#generics> {
+                pub todos: A,
+            }
+
+            #[derive(Debug)]
+            pub struct TodoGroupAssociationsOutput<#generics> {
+                pub todos: A,
+            }
+        );
+        let actual = structure.derive_structure_declaration();
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+}
@@ -0,0 +1,161 @@
+use darling::{ast::Fields, FromMeta};
+use proc_macro2::TokenStream;
+use syn::{Expr, Ident, Path, Type};
+
+use super::field::FactoryDeriveField;
+
+/// The options deriving `BuildResource` for the attributes of the factory
+#[derive(Default, FromMeta)]
+pub(crate) struct BuildResourceOptions {
+    /// When `output` is used, `BuildResource` is derived by moving every attribute to the
+    /// same-named field of the output.
+    output: Option<Type>,
+    /// The value of the `id` field of the output, e.g. `ctx.next_person_id()`.
+    /// The context is available as `ctx`. Requires `output`.
+    id: Option<Expr>,
+    /// The context `BuildResource` is derived for. When left unset, `BuildResource` is derived
+    /// for every context.
+    context: Option<Type>,
+    /// When `build_with` is used, `BuildResource` is derived by calling the given function with
+    /// the attributes and the context. Requires `output`.
+    build_with: Option<Path>,
+    /// When `memory` is used, `BuildResource` is derived for `fabriko::memory::MemoryStore` :
+    /// the `id` of the output defaults to the next identifier of its type, and the resource is
+    /// inserted in the store. Requires `output` and the `memory` feature of `fabriko`.
+    #[darling(default)]
+    memory: bool,
+}
+
+impl BuildResourceOptions {
+    /// Derives `BuildResource` for the attributes of the factory when `output` is used, either :
+    /// * by calling the `build_with` function with the attributes and the context,
+    /// * or by moving every attribute to the same-named field of the output, `id` being computed
+    ///   by the given expression.
+    pub(crate) fn derive_build_resource_implementation(
+        &self,
+        attributes_ident: &Ident,
+        fields: &Fields<FactoryDeriveField>,
+    ) -> darling::Result<Option<TokenStream>> {
+        let BuildResourceOptions {
+            output,
+            id,
+            context,
+            build_with,
+            memory,
+        } = self;
+        let output = match output {
+            Some(output) => output,
+            None if id.is_none() && build_with.is_none() && !memory => return Ok(None),
+            None => {
+                return Err(darling::Error::custom(
+                    "`id`, `build_with` and `memory` require the `output` of the factory",
+                )
+                .with_span(attributes_ident))
+            }
+        };
+        if *memory && (context.is_some() || build_with.is_some()) {
+            return Err(darling::Error::custom(
+                "`memory` can not be used with `context` or `build_with`",
+            )
+            .with_span(attributes_ident));
+        }
+
+        let build = match (build_with, id) {
+            (Some(_), Some(id)) => {
+                return Err(
+                    darling::Error::custom("`id` can not be used with `build_with`").with_span(id),
+                )
+            }
+            (Some(build_with), None) => quote::quote!(#build_with(self, ctx)),
+            (None, id) => {
+                let field_idents: Vec<&Option<Ident>> =
+                    fields.iter().map(|field| &field.ident).collect();
+                let id = match id {
+                    Some(id) => Some(quote::quote!(id: #id,)),
+                    None if *memory => Some(quote::quote!(id: ctx.next_id(),)),
+                    None => None,
+                };
+                // The memory store keeps a copy of the resource
+                let resource = if *memory {
+                    quote::quote!(ctx.insert(__resource))
+                } else {
+                    quote::quote!(__resource)
+                };
+                quote::quote!(
+                    let #attributes_ident {
+                        #(#field_idents,)*
+                    } = self;
+                    let __resource = #output {
+                        #id
+                        #(#field_idents,)*
+                    };
+                    Ok(#resource)
+                )
+            }
+        };
+
+        let (generics, context) = match (context, memory) {
+            (_, true) => (
+                TokenStream::new(),
+                quote::quote!(::fabriko::memory::MemoryStore),
+            ),
+            (Some(context), false) => (TokenStream::new(), quote::quote!(#context)),
+            (None, false) => (
+                quote::quote!(<CTX: ::fabriko::FactoryContext>),
+                quote::quote!(CTX),
+            ),
+        };
+        Ok(Some(quote::quote!(
+            impl #generics ::fabriko::BuildResource<#context> for #attributes_ident {
+                type Output = #output;
+
+                #[allow(unused_variables)]
+                fn build_resource(
+                    self,
+                    ctx: &mut #context,
+                ) -> Result<Self::Output, <#context as ::fabriko::FactoryContext>::Error> {
+                    #build
+                }
+            }
+        )))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use proc_macro2::Span;
+
+    use super::*;
+
+    #[test]
+    fn should_derive_build_resource_implementation_with_function() {
+        let options = BuildResourceOptions {
+            output: Some(syn::parse_quote!(Person)),
+            context: Some(syn::parse_quote!(TestContext)),
+            build_with: Some(syn::parse_quote!(Person::persist)),
+            ..Default::default()
+        };
+
+        let expected = quote::quote!(
+            impl ::fabriko::BuildResource<TestContext> for PersonDefinition {
+                type Output = Person;
+
+                #[allow(unused_variables)]
+                fn build_resource(
+                    self,
+                    ctx: &mut TestContext,
+                ) -> Result<Self::Output, <TestContext as ::fabriko::FactoryContext>::Error> {
+                    Person::persist(self, ctx)
+                }
+            }
+        );
+        let actual = options
+            .derive_build_resource_implementation(
+                &Ident::new("PersonDefinition", Span::call_site()),
+                &Fields::new(darling::ast::Style::Struct, Vec::new()),
+            )
+            .expect("Failed to derive BuildResource")
+            .expect("BuildResource should be derived");
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+}
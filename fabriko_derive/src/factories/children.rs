@@ -0,0 +1,295 @@
+use darling::{ast::Fields, FromMeta};
+use proc_macro2::TokenStream;
+use syn::Ident;
+
+use super::associations::belongs_to::{belonging_to_setter_ident, link_marker_ident};
+use super::field::FactoryDeriveField;
+use crate::bundles::attributes::BundleAttributes;
+
+/// A factory derived from the factory being derived, only starting from different defaults, e.g.
+/// `child(factory = "AdminFactory", defaults(role = "Role::Admin"))`.
+///
+/// The child wraps the parent factory : its setters, mixins, associations and `Factory`
+/// implementation are forwarded to the parent, so that both create the same resource.
+/// So are `strategy()` and `DynFactory` when the parent is derived with `proptest` or `dynamic`.
+#[derive(FromMeta)]
+pub(crate) struct ChildFactory {
+    factory: Ident,
+    /// The setters called on the default parent factory, in the order they are written
+    #[darling(default)]
+    defaults: BundleAttributes,
+}
+
+impl ChildFactory {
+    pub(crate) fn derive_child_factory(
+        &self,
+        parent_ident: &Ident,
+        fields: &Fields<FactoryDeriveField>,
+        with_associations: bool,
+        forwarded: ForwardedImplementations,
+    ) -> TokenStream {
+        let forwarded_implementations = forwarded.derive(&self.factory, parent_ident);
+        let ChildFactory { factory, defaults } = self;
+        let defaults = defaults.customization();
+        let setters: TokenStream = fields
+            .iter()
+            .filter(|field| field.should_derive_setter())
+            .map(|field| field.derive_child_setter(factory))
+            .collect();
+        let belonging_to_links: TokenStream = fields
+            .iter()
+            .filter(|field| field.belongs_to.is_some())
+            .map(|FactoryDeriveField { ident, ty, .. }| {
+                let ident = ident.as_ref().expect("Only named structs are supported");
+                let link_marker = link_marker_ident(parent_ident, ident);
+                quote::quote!(
+                    impl ::fabriko::BelongingToLink<#link_marker> for #factory {
+                        type ID = #ty;
+                        const SETTER: ::fabriko::FactorySetter<Self, Self::ID> = Self::#ident;
+                    }
+                )
+            })
+            .collect();
+        let with_related_resources = with_associations.then(|| {
+            quote::quote!(
+                impl ::fabriko::WithRelatedResources for #factory {
+                    type DefaultAssociations =
+                        <#parent_ident as ::fabriko::WithRelatedResources>::DefaultAssociations;
+                }
+            )
+        });
+
+        // The defaults of the child can be overridden as any other default
+        let default_parent = if cfg!(feature = "strict") {
+            quote::quote!({
+                let mut parent = #parent_ident::default() #defaults;
+                parent.__set_attributes = Default::default();
+                parent
+            })
+        } else {
+            quote::quote!(#parent_ident::default() #defaults)
+        };
+
+        quote::quote!(
+            pub struct #factory(#parent_ident);
+
+            impl Default for #factory {
+                fn default() -> Self {
+                    #factory(#default_parent)
+                }
+            }
+
+            impl From<#factory> for #parent_ident {
+                fn from(child: #factory) -> Self {
+                    child.0
+                }
+            }
+
+            impl #factory {
+                #setters
+            }
+
+            impl<M> ::fabriko::WithMixin<M> for #factory
+            where
+                #parent_ident: ::fabriko::WithMixin<M>,
+            {
+                fn with_mixin<F: FnOnce(M) -> M>(self, f: F) -> Self {
+                    #factory(self.0.with_mixin(f))
+                }
+            }
+
+            impl<CTX: ::fabriko::FactoryContext> ::fabriko::Factory<CTX> for #factory
+            where
+                #parent_ident: ::fabriko::Factory<CTX>,
+            {
+                type Output = <#parent_ident as ::fabriko::Factory<CTX>>::Output;
+
+                fn create(
+                    self,
+                    ctx: &mut CTX,
+                ) -> Result<Self::Output, ::fabriko::FabrikoError<CTX::Error>> {
+                    self.0.create(ctx)
+                }
+            }
+
+            #belonging_to_links
+            #with_related_resources
+            #forwarded_implementations
+        )
+    }
+}
+
+/// The optional implementations of the parent factory that its children forward
+#[derive(Clone, Copy)]
+pub(crate) struct ForwardedImplementations {
+    pub(crate) proptest: bool,
+    pub(crate) dynamic: bool,
+}
+
+impl ForwardedImplementations {
+    fn derive(self, child_ident: &Ident, parent_ident: &Ident) -> TokenStream {
+        // The arbitrary attributes of the parent start from the defaults of the child
+        let strategy = self.proptest.then(|| {
+            quote::quote!(
+                impl #child_ident {
+                    pub fn strategy() -> impl ::fabriko::proptest::strategy::Strategy<Value = Self> {
+                        ::fabriko::proptest::strategy::Strategy::prop_map(
+                            #parent_ident::strategy_from(|| #child_ident::default().0),
+                            #child_ident,
+                        )
+                    }
+                }
+
+                impl ::std::fmt::Debug for #child_ident {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        f.debug_tuple(stringify!(#child_ident)).field(&self.0).finish()
+                    }
+                }
+            )
+        });
+        let dyn_factory = self.dynamic.then(|| {
+            quote::quote!(
+                impl ::fabriko::dynamic::DynFactory for #child_ident {
+                    fn attributes() -> Vec<::fabriko::dynamic::DynAttribute> {
+                        <#parent_ident as ::fabriko::dynamic::DynFactory>::attributes()
+                    }
+
+                    fn set_any(
+                        self,
+                        attribute: &str,
+                        value: Box<dyn ::std::any::Any>,
+                    ) -> Result<Self, ::fabriko::dynamic::DynAttributeError> {
+                        ::fabriko::dynamic::DynFactory::set_any(self.0, attribute, value)
+                            .map(#child_ident)
+                    }
+                }
+            )
+        });
+        quote::quote!(#strategy #dyn_factory)
+    }
+}
+
+impl FactoryDeriveField {
+    /// The setters of the child, calling the same-named setters of the parent
+    fn derive_child_setter(&self, child_ident: &Ident) -> TokenStream {
+        let FactoryDeriveField {
+            ident,
+            ty,
+            into,
+            belongs_to,
+            ..
+        } = self;
+        let ident = ident.as_ref().expect("Only named structs are supported");
+        match belongs_to {
+            Some(belongs_to) => {
+                let factory = &belongs_to.factory;
+                let setter_belonging_to = belonging_to_setter_ident(ident);
+                quote::quote!(
+                    pub fn #setter_belonging_to<F: FnOnce(#factory) -> #factory>(self, f: F) -> Self {
+                        let #child_ident(parent) = self;
+                        #child_ident(parent.#setter_belonging_to(f))
+                    }
+                    pub fn #ident(self, id: #ty) -> Self {
+                        let #child_ident(parent) = self;
+                        #child_ident(parent.#ident(id))
+                    }
+                )
+            }
+            None if *into => quote::quote!(
+                pub fn #ident<T: Into<#ty>>(self, #ident: T) -> Self {
+                    let #child_ident(parent) = self;
+                    #child_ident(parent.#ident(#ident))
+                }
+            ),
+            None => quote::quote!(
+                pub fn #ident(self, #ident: #ty) -> Self {
+                    let #child_ident(parent) = self;
+                    #child_ident(parent.#ident(#ident))
+                }
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use darling::FromField;
+    use proc_macro2::Span;
+    use syn::Field;
+
+    use super::*;
+
+    #[test]
+    fn should_forward_setters_to_parent() {
+        let fields: Vec<FactoryDeriveField> = vec![
+            syn::parse_quote!(#[factory(into)] title: String),
+            syn::parse_quote!(done: bool),
+            syn::parse_quote!(#[factory(mixin)] timestamps: TimestampsMixin),
+        ]
+        .into_iter()
+        .map(|field: Field| FactoryDeriveField::from_field(&field).expect("Failed to parse field"))
+        .collect();
+        let child_ident = Ident::new("DoneTodoFactory", Span::call_site());
+
+        let expected = quote::quote!(
+            pub fn title<T: Into<String>>(self, title: T) -> Self {
+                let DoneTodoFactory(parent) = self;
+                DoneTodoFactory(parent.title(title))
+            }
+            pub fn done(self, done: bool) -> Self {
+                let DoneTodoFactory(parent) = self;
+                DoneTodoFactory(parent.done(done))
+            }
+        );
+        let actual: TokenStream = fields
+            .iter()
+            .filter(|field| field.should_derive_setter())
+            .map(|field| field.derive_child_setter(&child_ident))
+            .collect();
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[test]
+    fn should_forward_strategy_and_dyn_factory_to_parent() {
+        let child_ident = Ident::new("DoneTodoFactory", Span::call_site());
+        let parent_ident = Ident::new("TodoFactory", Span::call_site());
+
+        let expected = quote::quote!(
+            impl DoneTodoFactory {
+                pub fn strategy() -> impl ::fabriko::proptest::strategy::Strategy<Value = Self> {
+                    ::fabriko::proptest::strategy::Strategy::prop_map(
+                        TodoFactory::strategy_from(|| DoneTodoFactory::default().0),
+                        DoneTodoFactory,
+                    )
+                }
+            }
+
+            impl ::std::fmt::Debug for DoneTodoFactory {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.debug_tuple(stringify!(DoneTodoFactory)).field(&self.0).finish()
+                }
+            }
+
+            impl ::fabriko::dynamic::DynFactory for DoneTodoFactory {
+                fn attributes() -> Vec<::fabriko::dynamic::DynAttribute> {
+                    <TodoFactory as ::fabriko::dynamic::DynFactory>::attributes()
+                }
+
+                fn set_any(
+                    self,
+                    attribute: &str,
+                    value: Box<dyn ::std::any::Any>,
+                ) -> Result<Self, ::fabriko::dynamic::DynAttributeError> {
+                    ::fabriko::dynamic::DynFactory::set_any(self.0, attribute, value)
+                        .map(DoneTodoFactory)
+                }
+            }
+        );
+        let forwarded = ForwardedImplementations {
+            proptest: true,
+            dynamic: true,
+        };
+        let actual = forwarded.derive(&child_ident, &parent_ident);
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+}
@@ -0,0 +1,203 @@
+use darling::ast::Fields;
+use proc_macro2::TokenStream;
+use syn::Ident;
+
+use super::{associations::belongs_to::belonging_to_setter_ident, field::FactoryDeriveField};
+
+/// Derives `DynFactory` for the factory, listing its attributes and setting them by their name.
+///
+/// Attributes are set through their setter, except for mixins which are replaced as a whole.
+/// A `belongs_to` attribute accepts either an identifier or the factory of the resource.
+/// `dependant` attributes are computed from the other ones, and can not be set by their name.
+///
+/// The attributes whose type can be deserialized can also be set from a `serde_json::Value`,
+/// which is told apart at compile time by `fabriko::dynamic::probe`.
+pub(crate) fn derive_dyn_factory_implementation(
+    factory_ident: &Ident,
+    fields: &Fields<FactoryDeriveField>,
+) -> TokenStream {
+    let (attributes, setters): (TokenStream, TokenStream) = fields
+        .iter()
+        .filter(|field| field.dependant.is_none())
+        .map(|field| {
+            let ident = field
+                .ident
+                .as_ref()
+                .expect("Only named structs are supported");
+            let name = ident.to_string();
+            let resource_field = field
+                .belongs_to
+                .as_ref()
+                .and_then(|belongs_to| belongs_to.field.clone())
+                .unwrap_or_else(|| name.clone());
+            let ty = &field.ty;
+            let (kind, setter) = match &field.belongs_to {
+                _ if field.mixin => (
+                    quote::quote!(Mixin),
+                    quote::quote!({
+                        self.#ident =
+                            ::fabriko::dynamic::downcast_attribute::<#ty>(attribute, value)?;
+                        Ok(self)
+                    }),
+                ),
+                Some(belongs_to) => {
+                    let factory = &belongs_to.factory;
+                    let belonging_to = belonging_to_setter_ident(ident);
+                    (
+                        quote::quote!(BelongsTo),
+                        quote::quote!(match value.downcast::<#ty>() {
+                            Ok(id) => Ok(self.#ident(*id)),
+                            Err(value) => match value.downcast::<#factory>() {
+                                Ok(factory) => Ok(self.#belonging_to(|_| *factory)),
+                                Err(_) => Err(
+                                    ::fabriko::dynamic::DynAttributeError::type_mismatch::<#ty>(
+                                        attribute,
+                                    ),
+                                ),
+                            },
+                        }),
+                    )
+                }
+                None => (
+                    quote::quote!(Attribute),
+                    quote::quote!(Ok(self.#ident(
+                        ::fabriko::dynamic::downcast_attribute::<#ty>(attribute, value)?,
+                    ))),
+                ),
+            };
+            (
+                quote::quote!(::fabriko::dynamic::DynAttribute {
+                    name: #name,
+                    field: #resource_field,
+                    type_name: ::std::any::type_name::<#ty>(),
+                    kind: ::fabriko::dynamic::DynAttributeKind::#kind,
+                    deserializer: (&::fabriko::dynamic::probe::Probe::<#ty>::new()).deserializer(),
+                },),
+                quote::quote!(#name => #setter,),
+            )
+        })
+        .unzip();
+
+    quote::quote!(
+        impl ::fabriko::dynamic::DynFactory for #factory_ident {
+            #[allow(unused_imports, clippy::needless_borrow)]
+            fn attributes() -> Vec<::fabriko::dynamic::DynAttribute> {
+                use ::fabriko::dynamic::probe::{Deserializable as _, NotDeserializable as _};
+                vec![#attributes]
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn set_any(
+                mut self,
+                attribute: &str,
+                value: Box<dyn ::std::any::Any>,
+            ) -> Result<Self, ::fabriko::dynamic::DynAttributeError> {
+                match attribute {
+                    #setters
+                    _ => Err(::fabriko::dynamic::DynAttributeError::Unknown(
+                        attribute.to_string(),
+                    )),
+                }
+            }
+        }
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use darling::FromField;
+    use proc_macro2::Span;
+    use syn::Field;
+
+    use super::*;
+
+    #[test]
+    fn should_derive_dyn_factory_implementation() {
+        let fields: Vec<FactoryDeriveField> = vec![
+            syn::parse_quote!(#[factory(into)] title: String),
+            syn::parse_quote!(#[factory(belongs_to(factory = "TodoGroupFactory", field = "todo_group_id"))] todo_group: TodoGroupId),
+            syn::parse_quote!(#[factory(mixin)] timestamps: TimestampsMixin),
+            syn::parse_quote!(#[factory(dependant = "title.len()")] title_length: usize),
+        ]
+        .into_iter()
+        .map(|field: Field| FactoryDeriveField::from_field(&field).expect("Failed to parse field"))
+        .collect();
+        let fields = Fields::new(darling::ast::Style::Struct, fields);
+
+        let expected = quote::quote!(
+            impl ::fabriko::dynamic::DynFactory for TodoFactory {
+                #[allow(unused_imports, clippy::needless_borrow)]
+                fn attributes() -> Vec<::fabriko::dynamic::DynAttribute> {
+                    use ::fabriko::dynamic::probe::{Deserializable as _, NotDeserializable as _};
+                    vec![
+                        ::fabriko::dynamic::DynAttribute {
+                            name: "title",
+                            field: "title",
+                            type_name: ::std::any::type_name::<String>(),
+                            kind: ::fabriko::dynamic::DynAttributeKind::Attribute,
+                            deserializer: (&::fabriko::dynamic::probe::Probe::<String>::new())
+                                .deserializer(),
+                        },
+
+                        ::fabriko::dynamic::DynAttribute {
+                            name: "todo_group",
+                            field: "todo_group_id",
+                            type_name: ::std::any::type_name::<TodoGroupId>(),
+                            kind: ::fabriko::dynamic::DynAttributeKind::BelongsTo,
+                            deserializer: (&::fabriko::dynamic::probe::Probe::<TodoGroupId>::new())
+                                .deserializer(),
+                        },
+
+                        ::fabriko::dynamic::DynAttribute {
+                            name: "timestamps",
+                            field: "timestamps",
+                            type_name: ::std::any::type_name::<TimestampsMixin>(),
+                            kind: ::fabriko::dynamic::DynAttributeKind::Mixin,
+                            deserializer: (&::fabriko::dynamic::probe::Probe::<TimestampsMixin>::new())
+                                .deserializer(),
+                        },
+
+                    ]
+                }
+
+                #[allow(unused_mut, unused_variables)]
+                fn set_any(
+                    mut self,
+                    attribute: &str,
+                    value: Box<dyn ::std::any::Any>,
+                ) -> Result<Self, ::fabriko::dynamic::DynAttributeError> {
+                    match attribute {
+                        "title" => Ok(self.title(
+                            ::fabriko::dynamic::downcast_attribute::<String>(attribute, value)?,
+                        )),
+                        "todo_group" => match value.downcast::<TodoGroupId>() {
+                            Ok(id) => Ok(self.todo_group(*id)),
+                            Err(value) => match value.downcast::<TodoGroupFactory>() {
+                                Ok(factory) => Ok(self.belonging_to_todo_group(|_| *factory)),
+                                Err(_) => Err(
+                                    ::fabriko::dynamic::DynAttributeError::type_mismatch::<TodoGroupId>(
+                                        attribute,
+                                    ),
+                                ),
+                            },
+                        },
+                        "timestamps" => {
+                            self.timestamps = ::fabriko::dynamic::downcast_attribute::<TimestampsMixin>(
+                                attribute, value
+                            )?;
+                            Ok(self)
+                        },
+                        _ => Err(::fabriko::dynamic::DynAttributeError::Unknown(
+                            attribute.to_string(),
+                        )),
+                    }
+                }
+            }
+        );
+        let actual = derive_dyn_factory_implementation(
+            &Ident::new("TodoFactory", Span::call_site()),
+            &fields,
+        );
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+}
@@ -23,6 +23,22 @@ pub(crate) struct FactoryDeriveField {
     /// TODO: belongs_to and default are mutually exclusive
     pub(crate) default: Option<Expr>,
     pub(crate) belongs_to: Option<BelongsToAssociation>,
+    /// When `fake` is used, the field is generated by the given faker of the `fake` crate
+    /// when left unset, using the random number generator of the context.
+    /// Any expression evaluating to a faker, e.g. `faker::name::en::FirstName()` or `18..99`.
+    /// Requires the `fake` feature of `fabriko`.
+    pub(crate) fake: Option<Expr>,
+    /// The proptest strategy generating the field in the factories of `XFactory::strategy()`,
+    /// instead of `any::<T>()`. Only used when the factory is derived with `#[factory(proptest)]`.
+    pub(crate) proptest: Option<Expr>,
+    /// When `required` is used, the field has no default value : the factory only implements
+    /// `Factory` once the field is set, its state being tracked by a generic parameter.
+    #[darling(default)]
+    pub(crate) required: bool,
+    /// Set for the `default` fields of a factory derived with `#[factory(rng)]` :
+    /// their default value is only computed when creating the resource, when left unset.
+    #[darling(skip)]
+    pub(crate) lazy_default: bool,
 }
 
 impl FactoryDeriveField {
@@ -35,6 +51,12 @@ impl FactoryDeriveField {
         !self.mixin
     }
 
+    /// Whether the field is stored as an `Option` in the factory,
+    /// its value being computed when creating the resource if left unset.
+    pub(crate) fn is_unset_until_created(&self) -> bool {
+        self.fake.is_some() || self.lazy_default
+    }
+
     /// If this is an attribute field, returns a TokenStream to allow to destructure the field
     /// TODO: Rename
     pub(crate) fn as_factory_field(&self) -> Option<TokenStream> {
@@ -88,7 +110,11 @@ pub(crate) fn reassign_dependant_attributes(fields: &Fields<FactoryDeriveField>)
                  mixin: _,
                  into: _,
                  default: _,
+                 fake: _,
                  belongs_to: _,
+                 proptest: _,
+                 required: _,
+                 lazy_default: _,
              }| {
                 dependant.as_ref().map(|expr| {
                     quote::quote!(
@@ -99,3 +125,72 @@ pub(crate) fn reassign_dependant_attributes(fields: &Fields<FactoryDeriveField>)
         )
         .collect()
 }
+
+/// Writes a TokenStream that generates the value of every `fake` attribute left unset,
+/// and makes sure the context provides the random number generator to do so.
+pub(crate) fn resolve_fake_attributes_and_add_conditions(
+    impl_block_conditions: &mut Vec<TokenStream>,
+    fields: &Fields<FactoryDeriveField>,
+) -> TokenStream {
+    let fake_attributes: TokenStream = fields
+        .iter()
+        .filter_map(
+            |FactoryDeriveField {
+                 ident, ty, fake, ..
+             }| {
+                fake.as_ref().map(|faker| {
+                    quote::quote!(
+                        let #ident: #ty = match #ident {
+                            Some(#ident) => #ident,
+                            None => {
+                                #[allow(unused_imports)]
+                                use ::fabriko::fake::faker;
+                                ::fabriko::fake::Fake::fake_with_rng(
+                                    &(#faker),
+                                    ::fabriko::FactoryContextRng::rng(ctx),
+                                )
+                            }
+                        };
+                    )
+                })
+            },
+        )
+        .collect();
+    if !fake_attributes.is_empty() {
+        impl_block_conditions.push(quote::quote!(CTX: ::fabriko::FactoryContextRng,));
+    }
+    fake_attributes
+}
+
+/// Writes a TokenStream that computes the default value of every lazy `default` attribute
+/// left unset, after giving access to the random number generator of the context through `rng`.
+/// The `rng` variable stays available to the `dependant` attributes.
+pub(crate) fn resolve_lazy_defaults_and_add_conditions(
+    impl_block_conditions: &mut Vec<TokenStream>,
+    fields: &Fields<FactoryDeriveField>,
+) -> TokenStream {
+    let lazy_defaults: TokenStream = fields
+        .iter()
+        .filter(|field| field.lazy_default)
+        .filter_map(
+            |FactoryDeriveField {
+                 ident, ty, default, ..
+             }| {
+                default.as_ref().map(|expr| {
+                    quote::quote!(
+                        let #ident: #ty = match #ident {
+                            Some(#ident) => #ident,
+                            None => #expr,
+                        };
+                    )
+                })
+            },
+        )
+        .collect();
+    impl_block_conditions.push(quote::quote!(CTX: ::fabriko::FactoryContextRng,));
+    quote::quote!(
+        #[allow(unused_variables)]
+        let rng = ::fabriko::FactoryContextRng::rng(ctx);
+        #lazy_defaults
+    )
+}
@@ -3,27 +3,34 @@ use proc_macro2::TokenStream;
 use syn::Ident;
 
 use super::field::FactoryDeriveField;
+use super::required::RequiredAttributes;
 
 pub(crate) fn derive_mixin_implementations(
     factory_ident: &Ident,
     fields: &Fields<FactoryDeriveField>,
+    required: &RequiredAttributes,
 ) -> darling::Result<TokenStream> {
+    let generics = required.generics();
     let mixin_impls: TokenStream = fields
         .iter()
-        .flat_map(|field| field.derive_mixin_field(factory_ident))
+        .flat_map(|field| field.derive_mixin_field(factory_ident, &generics))
         .collect();
 
     Ok(mixin_impls)
 }
 
 impl FactoryDeriveField {
-    fn derive_mixin_field(&self, factory_ident: &Ident) -> Option<TokenStream> {
+    fn derive_mixin_field(
+        &self,
+        factory_ident: &Ident,
+        generics: &TokenStream,
+    ) -> Option<TokenStream> {
         let FactoryDeriveField {
             ident, ty, mixin, ..
         } = self;
         if *mixin {
             return Some(quote::quote! {
-                 impl ::fabriko::WithMixin<#ty> for #factory_ident {
+                 impl #generics ::fabriko::WithMixin<#ty> for #factory_ident #generics {
                      fn with_mixin<F: FnOnce(#ty) -> #ty>(mut self, f: F) -> Self {
                          self.#ident = f(self.#ident);
                          self
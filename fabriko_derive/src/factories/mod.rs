@@ -3,14 +3,22 @@ use darling::{
     FromDeriveInput,
 };
 use proc_macro2::TokenStream;
-use syn::{DeriveInput, Ident};
+use syn::{DeriveInput, Expr, Ident};
 
 use self::associations::{has_many::HasManyAssociation, has_one::HasOneAssociation};
+use self::build_resource::BuildResourceOptions;
+use self::children::{ChildFactory, ForwardedImplementations};
 use self::field::FactoryDeriveField;
+use self::required::RequiredAttributes;
 
 mod associations;
+mod build_resource;
+mod children;
+mod dynamic;
 mod field;
 mod mixins;
+mod proptest;
+mod required;
 mod setters;
 
 #[derive(FromDeriveInput)]
@@ -29,6 +37,26 @@ struct FactoryDeriveInput {
     has_many: Vec<HasManyAssociation>,
     #[darling(multiple)]
     has_one: Vec<HasOneAssociation>,
+    /// The factories deriving from this one, starting from different defaults, e.g.
+    /// `child(factory = "AdminFactory", defaults(role = "Role::Admin"))`.
+    #[darling(multiple, rename = "child")]
+    children: Vec<ChildFactory>,
+    /// When `rng` is used, the `default` and `dependant` expressions are evaluated when creating
+    /// the resource, and can use the random number generator of the context through `rng`.
+    /// Requires the `rand` feature of `fabriko`.
+    #[darling(default)]
+    rng: bool,
+    /// When `proptest` is used, `XFactory::strategy()` generates factories with arbitrary attributes.
+    /// Requires the `proptest` feature of `fabriko`.
+    #[darling(default)]
+    proptest: bool,
+    /// When `dynamic` is used, the attributes of the factory can be listed and set by their name,
+    /// with values of any type, or with JSON values through the `serde` feature of `fabriko`.
+    /// The factory can then create the resources described by fixture files.
+    #[darling(default)]
+    dynamic: bool,
+    #[darling(flatten)]
+    build_resource: BuildResourceOptions,
 }
 
 impl FactoryDeriveInput {
@@ -40,48 +68,124 @@ impl FactoryDeriveInput {
             associations_ident,
             has_many,
             has_one,
+            children,
+            rng,
+            proptest,
+            dynamic,
+            build_resource,
         } = self;
         let fields = match data {
             Data::Enum(_) => panic!("The only supported mode is struct with named fields"),
             Data::Struct(fields) => fields,
         };
+        check_fields(fields)?;
+        let required = RequiredAttributes::from_fields(fields);
+        if !required.is_empty() && (*proptest || *dynamic) {
+            return Err(darling::Error::custom(
+                "`required` attributes can not be used with `proptest` or `dynamic`",
+            )
+            .with_span(factory_ident));
+        }
+        if !required.is_empty() && !children.is_empty() {
+            return Err(darling::Error::custom(
+                "`required` attributes can not be used with `child` factories",
+            )
+            .with_span(factory_ident));
+        }
+        let build_resource_implementation =
+            build_resource.derive_build_resource_implementation(attributes_ident, fields)?;
 
         let mixin_implementations =
-            self::mixins::derive_mixin_implementations(factory_ident, fields)?;
+            self::mixins::derive_mixin_implementations(factory_ident, fields, &required)?;
         let setter_implementations =
-            self::setters::derive_setters_implementations(factory_ident, fields)?;
-        let factory_definition = derive_factory_definition(factory_ident, fields);
-        let factory_implementation =
-            derive_factory_implementation(attributes_ident, factory_ident, fields)?;
+            self::setters::derive_setters_implementations(factory_ident, fields, &required)?;
+        let factory_definition = derive_factory_definition(factory_ident, fields, &required);
+        let factory_implementation = derive_factory_implementation(
+            attributes_ident,
+            factory_ident,
+            fields,
+            &required,
+            *rng,
+        )?;
         let associated_resources_definition_and_implementation =
             associations_ident.as_ref().map(|associations_ident| {
                 self::associations::derive_associations(
                     has_many,
                     has_one,
-                    &associations_ident,
+                    associations_ident,
                     factory_ident,
+                    &required,
                 )
             });
+        let strategy_implementation =
+            proptest.then(|| self::proptest::derive_strategy_implementation(factory_ident, fields));
+        let dyn_factory_implementation = dynamic
+            .then(|| self::dynamic::derive_dyn_factory_implementation(factory_ident, fields));
         let belonging_to_link_implementations =
             self::associations::belongs_to::derive_belonging_to_link_implementations(
                 factory_ident,
                 fields,
+                &required,
             );
+        let forwarded = ForwardedImplementations {
+            proptest: *proptest,
+            dynamic: *dynamic,
+        };
+        let child_factories: TokenStream = children
+            .iter()
+            .map(|child| {
+                child.derive_child_factory(
+                    factory_ident,
+                    fields,
+                    associations_ident.is_some(),
+                    forwarded,
+                )
+            })
+            .collect();
 
         Ok(quote::quote! {
             #factory_definition
             #factory_implementation
             #mixin_implementations
             #setter_implementations
+            #strategy_implementation
+            #dyn_factory_implementation
+            #build_resource_implementation
             #belonging_to_link_implementations
             #associated_resources_definition_and_implementation
+            #child_factories
         })
     }
 }
 
+/// Rejects combinations of field attributes that can not be derived together
+fn check_fields(fields: &Fields<FactoryDeriveField>) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+    for field in fields.iter() {
+        if field.fake.is_some() && (field.default.is_some() || field.belongs_to.is_some()) {
+            errors.push(
+                darling::Error::custom("`fake` can not be used with `default` or `belongs_to`")
+                    .with_span(&field.ident),
+            );
+        }
+        let has_value =
+            field.default.is_some() || field.dependant.is_some() || field.fake.is_some();
+        if field.required && (has_value || field.mixin || field.belongs_to.is_some()) {
+            errors.push(
+                darling::Error::custom(
+                    "`required` can not be used with `default`, `dependant`, `fake`, `mixin` or `belongs_to`",
+                )
+                .with_span(&field.ident),
+            );
+        }
+    }
+    errors.finish()
+}
+
 fn derive_factory_definition(
     factory_ident: &Ident,
     fields: &Fields<FactoryDeriveField>,
+    required: &RequiredAttributes,
 ) -> TokenStream {
     let factory_fields: TokenStream = fields
         .iter()
@@ -94,10 +198,19 @@ fn derive_factory_definition(
                  dependant: _,
                  belongs_to,
                  default: _,
+                 fake,
+                 proptest: _,
+                 required: _,
+                 lazy_default,
              }| {
                 let ident = ident.as_ref().expect("Only named structs are supported");
+                if let Some(generic) = required.generic_of(ident) {
+                    return quote::quote!(#ident: #generic,);
+                }
                 match belongs_to {
                     Some(belongs_to) => belongs_to.field_definition(ident, ty),
+                    // Unset fake and lazy default attributes are computed when creating the resource
+                    None if fake.is_some() || *lazy_default => quote::quote!(#ident: Option<#ty>,),
                     None => quote::quote!(#ident: #ty,),
                 }
             },
@@ -115,24 +228,43 @@ fn derive_factory_definition(
                  dependant: _,
                  belongs_to: _,
                  default,
+                 fake: _,
+                 proptest: _,
+                 required: _,
+                 lazy_default,
              }| {
                 let ident = ident.as_ref().expect("Only named structs are supported");
+                if required.generic_of(ident).is_some() {
+                    return quote::quote!(#ident: ::fabriko::Unset,);
+                }
                 match default {
+                    Some(_) if *lazy_default => quote::quote!(#ident: None,),
                     Some(expr) => quote::quote!(#ident: #expr,),
                     None => quote::quote!(#ident: Default::default(),),
                 }
             },
         )
         .collect();
+    let declaration_generics = required.declaration_generics();
+    let (set_attributes_field, set_attributes_default) = if cfg!(feature = "strict") {
+        (
+            quote::quote!(__set_attributes: ::fabriko::strict::SetAttributes,),
+            quote::quote!(__set_attributes: Default::default(),),
+        )
+    } else {
+        (TokenStream::new(), TokenStream::new())
+    };
     quote::quote!(
-        pub struct #factory_ident {
+        pub struct #factory_ident #declaration_generics {
             #factory_fields
+            #set_attributes_field
         }
 
         impl Default for #factory_ident {
             fn default() -> Self {
                 #factory_ident {
                     #factory_default_fields
+                    #set_attributes_default
                 }
             }
         }
@@ -143,45 +275,83 @@ fn derive_factory_implementation(
     attributes_ident: &Ident,
     factory_ident: &Ident,
     fields: &Fields<FactoryDeriveField>,
+    required: &RequiredAttributes,
+    rng: bool,
 ) -> darling::Result<TokenStream> {
     let mut impl_block_conditions: Vec<TokenStream> = Vec::new();
 
     let destructured_factory_fields = self::field::destructure_factory_fields(fields);
+    let required_attributes =
+        required.resolve_required_attributes_and_add_conditions(&mut impl_block_conditions);
     let associations_pre_create =
         self::associations::belongs_to::resolve_belongs_to_assocations_and_add_conditions(
             &mut impl_block_conditions,
+            factory_ident,
             fields,
         );
+    let fake_attributes =
+        self::field::resolve_fake_attributes_and_add_conditions(&mut impl_block_conditions, fields);
+    let lazy_defaults = rng.then(|| {
+        self::field::resolve_lazy_defaults_and_add_conditions(&mut impl_block_conditions, fields)
+    });
     let reassign_dependant_attributes = self::field::reassign_dependant_attributes(fields);
     let destructured_attributes_fields = self::field::destructure_attributes_fields(fields);
 
     impl_block_conditions.push(quote::quote!(#attributes_ident: ::fabriko::BuildResource<CTX>,));
+    // Created resources are reported to the registry of the wrapper, which keys them by type
+    let record_created = cfg!(feature = "registry").then(|| {
+        impl_block_conditions.push(
+            quote::quote!(<#attributes_ident as ::fabriko::BuildResource<CTX>>::Output: 'static,),
+        );
+        quote::quote!(::fabriko::record_created(&__resource);)
+    });
     let where_clause: TokenStream = impl_block_conditions.into_iter().collect();
+    let factory_name = factory_ident.to_string();
+    let params = required.params();
+    let generics = required.generics();
     Ok(quote::quote! {
-        impl<CTX: ::fabriko::FactoryContext> ::fabriko::Factory<CTX> for #factory_ident
+        impl<CTX: ::fabriko::FactoryContext, #params> ::fabriko::Factory<CTX> for #factory_ident #generics
         where
             #where_clause
         {
             type Output = <#attributes_ident as ::fabriko::BuildResource<CTX>>::Output;
 
-            fn create(self, ctx: &mut CTX) -> Result<Self::Output, CTX::Error> {
+            fn create(
+                self,
+                ctx: &mut CTX,
+            ) -> Result<Self::Output, ::fabriko::FabrikoError<CTX::Error>> {
                 let #factory_ident {
                     #destructured_factory_fields
                     ..
                 } = self;
 
+                // Extracts required attributes
+                #required_attributes
+
                 // Resolves associations
-                use ::fabriko::ResolveDependency;
                 #associations_pre_create
 
+                // Generates unset fake attributes
+                #fake_attributes
+
+                // Computes unset lazy default attributes
+                #lazy_defaults
+
                 // Reassigns dependant attributes
                 #reassign_dependant_attributes
 
                 // Build resource
-                let __resource = #attributes_ident {
-                    #destructured_attributes_fields
-                }
-                .build_resource(ctx)?;
+                let __resource = ::fabriko::BuildResource::build_resource(
+                    #attributes_ident {
+                        #destructured_attributes_fields
+                    },
+                    ctx,
+                )
+                .map_err(|err| {
+                    ::fabriko::FabrikoError::new(err)
+                        .context(::fabriko::PathSegment::Factory(#factory_name))
+                })?;
+                #record_created
 
                 Ok(__resource)
             }
@@ -189,7 +359,55 @@ fn derive_factory_implementation(
     })
 }
 
+impl FactoryDeriveInput {
+    /// With `rng`, the default value of the attributes is computed when creating the resource
+    fn mark_lazy_defaults(&mut self) {
+        if self.rng {
+            if let Data::Struct(fields) = &mut self.data {
+                for field in fields.fields.iter_mut() {
+                    field.lazy_default =
+                        field.default.is_some() && !field.mixin && field.belongs_to.is_none();
+                }
+            }
+        }
+    }
+}
+
+/// Records that `setter` sets `attribute` of the factory, to report the attributes that are
+/// set more than once with the `strict` feature
+pub(crate) fn record_set_attribute(attribute: &str, setter: &str) -> Option<TokenStream> {
+    cfg!(feature = "strict")
+        .then(|| quote::quote!(self.__set_attributes.record::<Self>(#attribute, #setter);))
+}
+
 pub(crate) fn do_derive_factory(input: &DeriveInput) -> darling::Result<TokenStream> {
-    let factory_derive_input = FactoryDeriveInput::from_derive_input(input)?;
+    let mut factory_derive_input = FactoryDeriveInput::from_derive_input(input)?;
+    factory_derive_input.mark_lazy_defaults();
+    factory_derive_input.derive()
+}
+
+/// The value given to a field of a `factory!` definition
+pub(crate) enum DefinitionFieldValue {
+    Default(Expr),
+    Dependant(Expr),
+}
+
+/// Derives the factory of a `factory!` definition, whose fields can be given a value on top of
+/// their `#[factory(..)]` attributes, in the order of the fields of `input`.
+pub(crate) fn do_derive_factory_from_definition(
+    input: &DeriveInput,
+    values: Vec<Option<DefinitionFieldValue>>,
+) -> darling::Result<TokenStream> {
+    let mut factory_derive_input = FactoryDeriveInput::from_derive_input(input)?;
+    if let Data::Struct(fields) = &mut factory_derive_input.data {
+        for (field, value) in fields.fields.iter_mut().zip(values) {
+            match value {
+                Some(DefinitionFieldValue::Default(expr)) => field.default = Some(expr),
+                Some(DefinitionFieldValue::Dependant(expr)) => field.dependant = Some(expr),
+                None => {}
+            }
+        }
+    }
+    factory_derive_input.mark_lazy_defaults();
     factory_derive_input.derive()
 }
@@ -0,0 +1,154 @@
+use darling::ast::Fields;
+use proc_macro2::TokenStream;
+use syn::Ident;
+
+use super::field::FactoryDeriveField;
+
+impl FactoryDeriveField {
+    /// Whether the field is generated by the strategy of the factory.
+    /// Mixins, associations and dependant attributes keep their default value.
+    fn is_arbitrary(&self) -> bool {
+        !self.mixin && self.belongs_to.is_none() && self.dependant.is_none()
+    }
+}
+
+/// Derives `XFactory::strategy()`, generating factories whose attributes are arbitrary.
+///
+/// The attributes are generated by nested pairs of strategies, so that factories are not limited
+/// by the size of the tuples implementing `Strategy`.
+/// Since the values of a strategy have to implement `Debug`, the factory implements it too,
+/// displaying its arbitrary attributes.
+pub(crate) fn derive_strategy_implementation(
+    factory_ident: &Ident,
+    fields: &Fields<FactoryDeriveField>,
+) -> TokenStream {
+    let arbitrary_fields: Vec<&FactoryDeriveField> =
+        fields.iter().filter(|field| field.is_arbitrary()).collect();
+
+    let (strategies, pattern) = arbitrary_fields.iter().rev().fold(
+        (
+            quote::quote!(::fabriko::proptest::strategy::Just(())),
+            quote::quote!(()),
+        ),
+        |(strategies, pattern), field| {
+            let ident = &field.ident;
+            let ty = &field.ty;
+            let strategy = match &field.proptest {
+                Some(strategy) => quote::quote!(#strategy),
+                None => quote::quote!(::fabriko::proptest::arbitrary::any::<#ty>()),
+            };
+            (
+                quote::quote!((#strategy, #strategies)),
+                quote::quote!((#ident, #pattern)),
+            )
+        },
+    );
+    let assigned_fields: TokenStream = arbitrary_fields
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            if field.is_unset_until_created() {
+                quote::quote!(#ident: Some(#ident),)
+            } else {
+                quote::quote!(#ident,)
+            }
+        })
+        .collect();
+    let debug_fields: TokenStream = arbitrary_fields
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            quote::quote!(.field(stringify!(#ident), &self.#ident))
+        })
+        .collect();
+
+    quote::quote!(
+        impl #factory_ident {
+            pub fn strategy() -> impl ::fabriko::proptest::strategy::Strategy<Value = Self> {
+                Self::strategy_from(Self::default)
+            }
+
+            /// Generates factories starting from `default`, e.g. the defaults of a child factory
+            #[doc(hidden)]
+            pub fn strategy_from(
+                default: fn() -> Self,
+            ) -> impl ::fabriko::proptest::strategy::Strategy<Value = Self> {
+                ::fabriko::proptest::strategy::Strategy::prop_map(
+                    #strategies,
+                    move |#pattern| #factory_ident {
+                        #assigned_fields
+                        ..default()
+                    },
+                )
+            }
+        }
+
+        impl ::std::fmt::Debug for #factory_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(stringify!(#factory_ident))
+                    #debug_fields
+                    .finish_non_exhaustive()
+            }
+        }
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use darling::FromField;
+    use proc_macro2::Span;
+    use syn::Field;
+
+    use super::*;
+
+    #[test]
+    fn should_derive_strategy_implementation() {
+        let fields: Vec<FactoryDeriveField> = vec![
+            syn::parse_quote!(title: String),
+            syn::parse_quote!(#[factory(proptest = "0..10u32")] count: u32),
+            syn::parse_quote!(#[factory(mixin)] timestamps: TimestampsMixin),
+        ]
+        .into_iter()
+        .map(|field: Field| FactoryDeriveField::from_field(&field).expect("Failed to parse field"))
+        .collect();
+        let fields = Fields::new(darling::ast::Style::Struct, fields);
+
+        let expected = quote::quote!(
+            impl MyFactory {
+                pub fn strategy() -> impl ::fabriko::proptest::strategy::Strategy<Value = Self> {
+                    Self::strategy_from(Self::default)
+                }
+
+                /// Generates factories starting from `default`, e.g. the defaults of a child factory
+                #[doc(hidden)]
+                pub fn strategy_from(
+                    default: fn() -> Self,
+                ) -> impl ::fabriko::proptest::strategy::Strategy<Value = Self> {
+                    ::fabriko::proptest::strategy::Strategy::prop_map(
+                        (
+                            ::fabriko::proptest::arbitrary::any::<String>(),
+                            (0..10u32, ::fabriko::proptest::strategy::Just(()))
+                        ),
+                        move |(title, (count, ()))| MyFactory {
+                            title,
+                            count,
+                            ..default()
+                        },
+                    )
+                }
+            }
+
+            impl ::std::fmt::Debug for MyFactory {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.debug_struct(stringify!(MyFactory))
+                        .field(stringify!(title), &self.title)
+                        .field(stringify!(count), &self.count)
+                        .finish_non_exhaustive()
+                }
+            }
+        );
+        let actual =
+            derive_strategy_implementation(&Ident::new("MyFactory", Span::call_site()), &fields);
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+}
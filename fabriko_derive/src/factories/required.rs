@@ -0,0 +1,165 @@
+use darling::ast::Fields;
+use proc_macro2::TokenStream;
+use syn::{Ident, Type};
+
+use super::field::FactoryDeriveField;
+
+/// The `#[factory(required)]` attributes of a factory.
+///
+/// The factory gets a generic parameter for each one of them, e.g.
+/// `UserFactory<__Required0 = ::fabriko::Unset>`, which is the type of the field in the factory :
+/// `::fabriko::Unset` until the attribute is set, the type of the attribute afterwards.
+/// `Factory` is only implemented once all of them are set.
+/// The parameters are prefixed so that they do not collide with the types of the attributes.
+pub(crate) struct RequiredAttributes<'a> {
+    attributes: Vec<RequiredAttribute<'a>>,
+}
+
+struct RequiredAttribute<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    generic: Ident,
+}
+
+impl<'a> RequiredAttributes<'a> {
+    pub(crate) fn from_fields(fields: &'a Fields<FactoryDeriveField>) -> Self {
+        let attributes = fields
+            .iter()
+            .filter(|field| field.required)
+            .enumerate()
+            .map(|(index, field)| RequiredAttribute {
+                ident: field
+                    .ident
+                    .as_ref()
+                    .expect("Only named structs are supported"),
+                ty: &field.ty,
+                generic: quote::format_ident!("__Required{index}"),
+            })
+            .collect();
+        RequiredAttributes { attributes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// The generic parameter tracking the state of the field, if it is required
+    pub(crate) fn generic_of(&self, ident: &Ident) -> Option<&Ident> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.ident == ident)
+            .map(|attribute| &attribute.generic)
+    }
+
+    /// The generic parameters of the factory, e.g. `__Required0, __Required1,`
+    pub(crate) fn params(&self) -> TokenStream {
+        let generics = self.attributes.iter().map(|attribute| &attribute.generic);
+        quote::quote!(#(#generics,)*)
+    }
+
+    /// The generic parameters of the factory in any state, e.g. `<__Required0, __Required1,>`,
+    /// to implement
+    /// something for all of them : `impl #generics Trait for UserFactory #generics`
+    pub(crate) fn generics(&self) -> TokenStream {
+        if self.is_empty() {
+            return TokenStream::new();
+        }
+        let params = self.params();
+        quote::quote!(<#params>)
+    }
+
+    /// The generic parameters of the declaration of the factory, unset by default
+    pub(crate) fn declaration_generics(&self) -> TokenStream {
+        if self.is_empty() {
+            return TokenStream::new();
+        }
+        let generics = self.attributes.iter().map(|attribute| &attribute.generic);
+        quote::quote!(<#(#generics = ::fabriko::Unset,)*>)
+    }
+
+    /// The generic parameters of the factory once the attribute `ident` is set
+    pub(crate) fn generics_once_set(&self, ident: &Ident) -> TokenStream {
+        let generics = self.attributes.iter().map(|attribute| {
+            let RequiredAttribute {
+                ident: attribute_ident,
+                ty,
+                generic,
+            } = attribute;
+            if *attribute_ident == ident {
+                quote::quote!(#ty)
+            } else {
+                quote::quote!(#generic)
+            }
+        });
+        quote::quote!(<#(#generics,)*>)
+    }
+
+    /// Writes a TokenStream that extracts the value of every required attribute,
+    /// and makes sure they are all set for the factory to implement `Factory`.
+    /// The conditions point to the fields, so that the compile error names the missing attribute.
+    pub(crate) fn resolve_required_attributes_and_add_conditions(
+        &self,
+        impl_block_conditions: &mut Vec<TokenStream>,
+    ) -> TokenStream {
+        self.attributes
+            .iter()
+            .map(|RequiredAttribute { ident, ty, generic }| {
+                impl_block_conditions.push(quote::quote_spanned!(ident.span()=>
+                    #generic: ::fabriko::Required<#ty>,
+                ));
+                quote::quote!(
+                    let #ident: #ty = ::fabriko::Required::into_value(#ident);
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use darling::FromField;
+    use syn::Field;
+
+    use super::*;
+
+    #[test]
+    fn should_track_required_attributes_with_generics() {
+        let fields: Vec<FactoryDeriveField> = vec![
+            syn::parse_quote!(#[factory(required)] email: String),
+            syn::parse_quote!(name: String),
+            syn::parse_quote!(#[factory(required)] password: String),
+        ]
+        .into_iter()
+        .map(|field: Field| FactoryDeriveField::from_field(&field).expect("Failed to parse field"))
+        .collect();
+        let fields = Fields::new(darling::ast::Style::Struct, fields);
+        let required = RequiredAttributes::from_fields(&fields);
+        let password: Ident = syn::parse_quote!(password);
+
+        assert_eq!(
+            required.declaration_generics().to_string(),
+            quote::quote!(<__Required0 = ::fabriko::Unset, __Required1 = ::fabriko::Unset,>)
+                .to_string()
+        );
+        assert_eq!(
+            required.generics_once_set(&password).to_string(),
+            quote::quote!(<__Required0, String,>).to_string()
+        );
+
+        let mut conditions = Vec::new();
+        let expected = quote::quote!(
+            let email: String = ::fabriko::Required::into_value(email);
+            let password: String = ::fabriko::Required::into_value(password);
+        );
+        let actual = required.resolve_required_attributes_and_add_conditions(&mut conditions);
+        assert_eq!(expected.to_string(), actual.to_string());
+        assert_eq!(
+            conditions.into_iter().collect::<TokenStream>().to_string(),
+            quote::quote!(
+                __Required0: ::fabriko::Required<String>,
+                __Required1: ::fabriko::Required<String>,
+            )
+            .to_string()
+        );
+    }
+}
@@ -3,6 +3,7 @@ use proc_macro2::TokenStream;
 use syn::Ident;
 
 use super::field::FactoryDeriveField;
+use super::required::RequiredAttributes;
 
 impl FactoryDeriveField {
     pub(crate) fn derive_setter(&self) -> Option<TokenStream> {
@@ -15,27 +16,46 @@ impl FactoryDeriveField {
                 mixin: _,
                 dependant: _,
                 default: _,
+                fake: _,
+                proptest: _,
+                required: _,
+                lazy_default: _,
             } = self;
             match belongs_to {
                 Some(belongs_to) => {
                     return Some(
                         super::associations::belongs_to::declare_fields_belonging_to(
-                            belongs_to, ident,
+                            belongs_to, ident, ty,
                         ),
                     );
                 }
                 None => {
+                    // Fake and lazy default attributes are stored as an Option, unset until the setter is called
+                    let value = if *into {
+                        quote::quote!(#ident.into())
+                    } else {
+                        quote::quote!(#ident)
+                    };
+                    let value = if self.is_unset_until_created() {
+                        quote::quote!(Some(#value))
+                    } else {
+                        value
+                    };
+                    let name = ident.as_ref().map(Ident::to_string).unwrap_or_default();
+                    let record = super::record_set_attribute(&name, &name);
                     if *into {
                         return Some(quote::quote!(
                             pub fn #ident<T: Into<#ty>>(mut self, #ident: T) -> Self {
-                                self.#ident = #ident.into();
+                                #record
+                                self.#ident = #value;
                                 self
                             }
                         ));
                     } else {
                         return Some(quote::quote!(
                             pub fn #ident(mut self, #ident: #ty) -> Self {
-                                self.#ident = #ident;
+                                #record
+                                self.#ident = #value;
                                 self
                             }
                         ));
@@ -47,16 +67,80 @@ impl FactoryDeriveField {
     }
 }
 
+/// The setter of a required attribute moves every field to the factory in which the attribute
+/// is set, e.g. `UserFactory<A, B> -> UserFactory<String, B>`
+fn derive_required_setter(
+    factory_ident: &Ident,
+    fields: &Fields<FactoryDeriveField>,
+    required: &RequiredAttributes,
+    field: &FactoryDeriveField,
+) -> TokenStream {
+    let FactoryDeriveField {
+        ident, ty, into, ..
+    } = field;
+    let ident = ident.as_ref().expect("Only named structs are supported");
+    let name = ident.to_string();
+    let other_fields: Vec<&Ident> = fields
+        .iter()
+        .filter_map(|field| field.ident.as_ref())
+        .filter(|other| *other != ident)
+        .collect();
+    let generics_once_set = required.generics_once_set(ident);
+    let (generics, argument, value) = if *into {
+        (
+            quote::quote!(<T: Into<#ty>>),
+            quote::quote!(T),
+            quote::quote!(#ident.into()),
+        )
+    } else {
+        (
+            TokenStream::new(),
+            quote::quote!(#ty),
+            quote::quote!(#ident),
+        )
+    };
+    let record = super::record_set_attribute(&name, &name);
+    let set_attributes = cfg!(feature = "strict").then(|| quote::quote!(__set_attributes,));
+    quote::quote!(
+        pub fn #ident #generics(mut self, #ident: #argument) -> #factory_ident #generics_once_set {
+            #record
+            let #factory_ident {
+                #(#other_fields,)*
+                #ident: _,
+                #set_attributes
+            } = self;
+            #factory_ident {
+                #(#other_fields,)*
+                #ident: #value,
+                #set_attributes
+            }
+        }
+    )
+}
+
 pub(crate) fn derive_setters_implementations(
     factory_ident: &Ident,
     fields: &Fields<FactoryDeriveField>,
+    required: &RequiredAttributes,
 ) -> darling::Result<TokenStream> {
     let setters: TokenStream = fields
         .iter()
-        .flat_map(|field| field.derive_setter())
+        .flat_map(|field| {
+            if field.required {
+                Some(derive_required_setter(
+                    factory_ident,
+                    fields,
+                    required,
+                    field,
+                ))
+            } else {
+                field.derive_setter()
+            }
+        })
         .collect();
+    let generics = required.generics();
     Ok(quote::quote! {
-        impl #factory_ident {
+        impl #generics #factory_ident #generics {
             #setters
         }
     })
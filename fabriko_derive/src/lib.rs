@@ -1,5 +1,6 @@
 extern crate proc_macro;
 use bundles::do_derive_bundle;
+use definitions::do_derive_factory_definition;
 use fabriko::do_derive_fabriko;
 use factories::do_derive_factory;
 use identifier::do_derive_with_identifier;
@@ -8,6 +9,7 @@ use proc_macro::TokenStream;
 use syn::DeriveInput;
 
 mod bundles;
+mod definitions;
 mod fabriko;
 mod factories;
 mod identifier;
@@ -48,6 +50,27 @@ pub fn derive_with_identifier(item: TokenStream) -> TokenStream {
     unwrap_errors(token_stream)
 }
 
+/// Declares a definition, its factory and how the resource is built in one place :
+///
+/// ```ignore
+/// fabriko::factory! {
+///     UserFactory for User in TestContext {
+///         #[factory(into)]
+///         name: String = "Alice".into(),
+///         email: String = dependant(format!("{name}@test.com")),
+///     } => |definition, ctx| { .. }
+/// }
+/// ```
+///
+/// The definition structure is named after the factory (`UserDefinition`), and its fields accept
+/// the same `#[factory(..)]` attributes as `#[derive(Factory)]`. The closure builds the resource
+/// from the definition and the context, as `BuildResource::build_resource` would.
+#[proc_macro]
+pub fn factory(item: TokenStream) -> TokenStream {
+    let token_stream = do_derive_factory_definition(item.into());
+    unwrap_errors(token_stream)
+}
+
 fn unwrap_errors(res: darling::Result<proc_macro2::TokenStream>) -> TokenStream {
     match res {
         Ok(tt) => tt,